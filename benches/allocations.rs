@@ -0,0 +1,68 @@
+//! Count the heap allocations made negotiating browser default headers.
+//!
+//! Run with and without the `smallvec` feature to compare:
+//!
+//! ```text
+//! cargo bench --bench allocations
+//! cargo bench --bench allocations --features smallvec
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const PROVIDED: &[&str] = &["UTF-8", "ISO-8859-1", "windows-1252"];
+
+const HEADERS: &[&str] = &[
+    "ISO-8859-1,utf-8;q=0.7,*;q=0.3",
+    "utf-8, iso-8859-1;q=0.5",
+    "windows-1252;q=0.9, UTF-8;q=0.8, *;q=0.1",
+];
+
+fn count(f: impl Fn()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn main() {
+    for header in HEADERS {
+        let preferred = count(|| {
+            negotiator::charset::preferred(Some(header), PROVIDED);
+        });
+        let most_preferred = count(|| {
+            negotiator::charset::most_preferred(Some(header), PROVIDED);
+        });
+        let preferred_ref = count(|| {
+            negotiator::charset::preferred_ref(Some(header), PROVIDED);
+        });
+        let most_preferred_ref = count(|| {
+            negotiator::charset::most_preferred_ref(Some(header), PROVIDED);
+        });
+        println!(
+            "{:45} preferred: {:4} allocations, most_preferred: {:4} allocations",
+            header, preferred, most_preferred
+        );
+        println!(
+            "{:45} preferred_ref: {:4}, most_preferred_ref: {:4}",
+            "", preferred_ref, most_preferred_ref
+        );
+    }
+}
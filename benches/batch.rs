@@ -0,0 +1,42 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+const PROVIDED: &[&str] = &["UTF-8", "ISO-8859-1", "windows-1252"];
+
+const HEADERS: &[Option<&str>] = &[
+    None,
+    Some("*"),
+    Some("UTF-8"),
+    Some("ISO-8859-1,utf-8;q=0.7,*;q=0.3"),
+    Some("utf-8, iso-8859-1;q=0.5"),
+    Some("windows-1252;q=0.9, UTF-8;q=0.8, *;q=0.1"),
+    Some("KOI8-R"),
+];
+
+fn synthetic_headers(count: usize) -> Vec<Option<&'static str>> {
+    HEADERS.iter().copied().cycle().take(count).collect()
+}
+
+fn bench_batch(c: &mut Criterion) {
+    let headers = synthetic_headers(100_000);
+
+    let mut group = c.benchmark_group("batch");
+    group.throughput(Throughput::Elements(headers.len() as u64));
+    group.sample_size(10);
+    group.bench_function("charset::batch 100k", |b| {
+        b.iter(|| negotiator::charset::batch(headers.iter().copied(), black_box(PROVIDED)))
+    });
+    group.bench_function("charset::most_preferred 100k", |b| {
+        b.iter(|| {
+            for header in &headers {
+                black_box(negotiator::charset::most_preferred(
+                    *header,
+                    black_box(PROVIDED),
+                ));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_batch);
+criterion_main!(benches);
@@ -0,0 +1,107 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const PROVIDED: &[&str] = &["UTF-8", "ISO-8859-1", "windows-1252"];
+
+/// Accept-Charset as sent by older desktop browsers.
+const BROWSER: &str = "ISO-8859-1,utf-8;q=0.7,*;q=0.3";
+
+/// Accept-Charset with a preference between two charsets.
+const TYPICAL: &str = "UTF-8;q=0.9, ISO-8859-1;q=0.8";
+
+/// Accept-Charset as sent by typical API clients.
+const API_CLIENT: &str = "utf-8";
+
+/// Accept-Encoding as sent by current browsers.
+const BROWSER_ENCODING: &str = "gzip, deflate, br, zstd";
+
+const WANT_DIGEST: &str = "sha-256;q=1, unixsum;q=0.1";
+
+fn pathological() -> String {
+    (0..200)
+        .map(|i| format!("charset-{};q=0.{};a=1;b=2", i, i % 10))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn bench_charset(c: &mut Criterion) {
+    let pathological = pathological();
+
+    let mut group = c.benchmark_group("charset");
+    for &(name, header) in &[
+        ("browser", BROWSER),
+        ("typical", TYPICAL),
+        ("api client", API_CLIENT),
+        ("pathological", pathological.as_str()),
+    ] {
+        group.bench_function(format!("parse {}", name), |b| {
+            b.iter(|| negotiator::charset::parse(black_box(header)))
+        });
+        group.bench_function(format!("preferred {}", name), |b| {
+            b.iter(|| negotiator::charset::preferred(black_box(Some(header)), PROVIDED))
+        });
+        group.bench_function(format!("most_preferred {}", name), |b| {
+            b.iter(|| negotiator::charset::most_preferred(black_box(Some(header)), PROVIDED))
+        });
+    }
+    let long = pathological.replace("charset-199", "UTF-8");
+    group.bench_function("most_preferred long header", |b| {
+        b.iter(|| negotiator::charset::most_preferred(black_box(Some(&long)), PROVIDED))
+    });
+    group.bench_function("preferred long header", |b| {
+        b.iter(|| negotiator::charset::preferred(black_box(Some(&long)), PROVIDED))
+    });
+    group.finish();
+}
+
+fn bench_tokens(c: &mut Criterion) {
+    let params = "sha-256;a=1;b=2;c;d=;q=0.9, unixsum;x=y;q=0.1;z=1, md5;q=0;k=v;k=w";
+
+    let mut group = c.benchmark_group("tokens");
+    group.bench_function("parse parameter heavy", |b| {
+        b.iter(|| negotiator::parse_tokens(black_box(params)))
+    });
+    group.bench_function("preferred want-digest", |b| {
+        b.iter(|| negotiator::preferred_tokens(black_box(Some(WANT_DIGEST)), &["md5", "sha-256"]))
+    });
+    group.finish();
+}
+
+fn bench_encodings(c: &mut Criterion) {
+    let provided = &["zstd", "br", "gzip"];
+
+    let mut group = c.benchmark_group("encodings");
+    group.bench_function("parse browser", |b| {
+        b.iter(|| negotiator::encoding::parse(black_box(BROWSER_ENCODING)))
+    });
+    group.bench_function("parse browser, general parser", |b| {
+        b.iter(|| negotiator::parse_tokens(black_box(BROWSER_ENCODING)))
+    });
+    group.bench_function("most_preferred browser", |b| {
+        b.iter(|| negotiator::encoding::most_preferred(black_box(Some(BROWSER_ENCODING)), provided))
+    });
+    group.finish();
+}
+
+fn bench_matching(c: &mut Criterion) {
+    let provided: Vec<String> = (0..50).map(|i| format!("X-CHARSET-{}", i)).collect();
+    let provided: Vec<&str> = provided.iter().map(|p| p.as_str()).collect();
+    let header: String = (0..50)
+        .map(|i| format!("x-charset-{};q=0.5", i))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let mut group = c.benchmark_group("matching");
+    group.bench_function("mixed case 50x50", |b| {
+        b.iter(|| negotiator::charset::preferred(black_box(Some(&header)), black_box(&provided)))
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_charset,
+    bench_tokens,
+    bench_encodings,
+    bench_matching
+);
+criterion_main!(benches);
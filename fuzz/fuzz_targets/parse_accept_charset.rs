@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use negotiator::charset::AcceptCharsetHeader;
+
+fuzz_target!(|header: &str| {
+    let parsed = AcceptCharsetHeader::parse(header);
+    let _ = parsed.to_string();
+    let _ = negotiator::header_stats(header, negotiator::HeaderKind::AcceptCharset);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|header: &str| {
+    for token in negotiator::parse_tokens(header) {
+        let _ = token.to_string();
+    }
+    let _ = negotiator::header_stats(header, negotiator::HeaderKind::TokenList);
+});
@@ -0,0 +1,25 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The first line is the header, the rest are provided charsets.
+fuzz_target!(|data: &str| {
+    let mut lines = data.lines();
+    let header = lines.next();
+    let provided: Vec<&str> = lines.take(8).collect();
+
+    let _ = negotiator::charset::preferred(header, &provided);
+    let _ = negotiator::charset::preferred_with_quality(header, &provided);
+    let _ = negotiator::charset::most_preferred(header, &provided);
+    let _ = negotiator::charset::batch(vec![header], &provided);
+    let _ = negotiator::charset::acceptable(header);
+    let _ = negotiator::charset::rejected(header, &provided);
+    let _ = negotiator::charset::explain(header, &provided);
+    let _ = negotiator::charset::negotiate(header, &provided);
+    let _ = negotiator::charset::preferred_with_aliases(header, &provided);
+    let _ = negotiator::charset::preferred_with_implicit_latin1(header, &provided);
+    let _ = negotiator::charset::preferred_with_min_quality(header, &provided, 0.5);
+    let _ = negotiator::charset::try_preferred_with_options(header, &provided, &Default::default());
+    for charset in &provided {
+        let _ = negotiator::charset::is_acceptable(header, charset);
+    }
+});
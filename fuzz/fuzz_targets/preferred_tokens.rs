@@ -0,0 +1,19 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use negotiator::TokenOptions;
+
+// The first line is the header, the rest are provided tokens.
+fuzz_target!(|data: &str| {
+    let mut lines = data.lines();
+    let header = lines.next();
+    let provided: Vec<&str> = lines.take(8).collect();
+
+    let _ = negotiator::preferred_tokens(header, &provided);
+    let wildcard = TokenOptions {
+        wildcard: true,
+        ..TokenOptions::default()
+    };
+    let _ = negotiator::preferred_tokens_with_quality(header, &provided, &wildcard);
+    let _ = negotiator::preferred_token_where(header, &provided, &wildcard, |_| true);
+});
@@ -0,0 +1,258 @@
+//! Show what a request's negotiation headers would get.
+//!
+//! Built with the `cli` feature:
+//!
+//! ```text
+//! negotiator --accept-charset 'UTF-8;q=0.5, *' --charsets UTF-8,ISO-8859-1
+//! negotiator --accept-encoding 'gzip, br' --encodings br,gzip --te gzip --transfer-encodings gzip
+//! printf 'Accept-Charset: UTF-8\n' | negotiator --stdin --charsets UTF-8 --json
+//! ```
+//!
+//! Accept-Language and Accept negotiation are not implemented yet.
+
+use std::env;
+use std::io::{self, BufRead};
+use std::process;
+
+const USAGE: &str =
+    "usage: negotiator [--stdin] [--json] [--accept-charset VALUE] [--charsets LIST]
+                  [--accept-encoding VALUE] [--encodings LIST]
+                  [--te VALUE] [--transfer-encodings LIST]
+
+  --accept-charset VALUE     an Accept-Charset header value, may be repeated
+  --charsets LIST            comma separated charsets to negotiate against
+  --accept-encoding VALUE    an Accept-Encoding header value, may be repeated
+  --encodings LIST           comma separated content codings to negotiate against
+  --te VALUE                 a TE header value, may be repeated
+  --transfer-encodings LIST  comma separated transfer codings to negotiate against
+  --stdin                    also read \"Name: value\" header lines from stdin
+  --json                     print the results as JSON";
+
+#[derive(Default)]
+struct Args {
+    headers: Vec<(String, String)>,
+    charsets: Option<Vec<String>>,
+    encodings: Option<Vec<String>>,
+    transfer_encodings: Option<Vec<String>>,
+    stdin: bool,
+    json: bool,
+}
+
+type PreferredFn = fn(Option<&str>, &[&str]) -> Vec<(String, f32)>;
+
+struct Axis {
+    header: &'static str,
+    value: Option<String>,
+    selected: Option<String>,
+    preferred: Vec<(String, f32)>,
+}
+
+fn main() {
+    let mut args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if args.stdin {
+        for line in io::stdin().lock().lines() {
+            let line = line.unwrap_or_else(|err| {
+                eprintln!("failed to read stdin: {}", err);
+                process::exit(1);
+            });
+            if let Some((name, value)) = line.split_once(':') {
+                args.headers
+                    .push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+    }
+
+    let headers: Vec<(&str, &str)> = args
+        .headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+
+    let mut axes = Vec::new();
+    if let Some(charsets) = &args.charsets {
+        axes.push(negotiate(
+            &headers,
+            "Accept-Charset",
+            charsets,
+            negotiator::charset::most_preferred,
+            negotiator::charset::preferred_with_quality,
+        ));
+    }
+    if let Some(encodings) = &args.encodings {
+        axes.push(negotiate(
+            &headers,
+            "Accept-Encoding",
+            encodings,
+            negotiator::encoding::most_preferred,
+            negotiator::encoding::preferred_with_quality,
+        ));
+    }
+    if let Some(transfer_encodings) = &args.transfer_encodings {
+        axes.push(negotiate(
+            &headers,
+            "TE",
+            transfer_encodings,
+            negotiator::transfer_encoding::most_preferred,
+            negotiator::transfer_encoding::preferred_with_quality,
+        ));
+    }
+
+    if args.json {
+        print_json(&axes);
+    } else {
+        print_text(&axes);
+    }
+}
+
+fn parse_args(mut argv: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args::default();
+
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--accept-charset" => {
+                let value = argv.next().ok_or("--accept-charset needs a value")?;
+                args.headers.push(("Accept-Charset".to_string(), value));
+            }
+            "--charsets" => {
+                let list = argv.next().ok_or("--charsets needs a value")?;
+                args.charsets = Some(split_list(&list));
+            }
+            "--accept-encoding" => {
+                let value = argv.next().ok_or("--accept-encoding needs a value")?;
+                args.headers.push(("Accept-Encoding".to_string(), value));
+            }
+            "--encodings" => {
+                let list = argv.next().ok_or("--encodings needs a value")?;
+                args.encodings = Some(split_list(&list));
+            }
+            "--te" => {
+                let value = argv.next().ok_or("--te needs a value")?;
+                args.headers.push(("TE".to_string(), value));
+            }
+            "--transfer-encodings" => {
+                let list = argv.next().ok_or("--transfer-encodings needs a value")?;
+                args.transfer_encodings = Some(split_list(&list));
+            }
+            "--stdin" => args.stdin = true,
+            "--json" => args.json = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+
+    if args.charsets.is_none() && args.encodings.is_none() && args.transfer_encodings.is_none() {
+        return Err(
+            "nothing to negotiate, pass --charsets, --encodings or --transfer-encodings"
+                .to_string(),
+        );
+    }
+
+    Ok(args)
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_string())
+        .collect()
+}
+
+fn negotiate(
+    headers: &[(&str, &str)],
+    header: &'static str,
+    provided: &[String],
+    selected: fn(Option<&str>, &[&str]) -> Option<String>,
+    preferred: PreferredFn,
+) -> Axis {
+    let provided: Vec<&str> = provided.iter().map(|p| p.as_str()).collect();
+    let value = negotiator::combined_header(headers, header);
+
+    Axis {
+        header,
+        selected: selected(value.as_deref(), &provided),
+        preferred: preferred(value.as_deref(), &provided),
+        value,
+    }
+}
+
+fn vary(axes: &[Axis]) -> String {
+    let names: Vec<&str> = axes.iter().map(|axis| axis.header).collect();
+    names.join(", ")
+}
+
+fn print_text(axes: &[Axis]) {
+    for axis in axes {
+        match &axis.value {
+            Some(value) => println!("{}: {}", axis.header, value),
+            None => println!("{}: (none)", axis.header),
+        }
+        match &axis.selected {
+            Some(selected) => println!("  selected: {}", selected),
+            None => println!("  selected: (not acceptable)"),
+        }
+        for (value, q) in &axis.preferred {
+            println!("  {} q={}", value, q);
+        }
+    }
+    println!("Vary: {}", vary(axes));
+}
+
+fn print_json(axes: &[Axis]) {
+    let vary = vary(axes);
+    let axes: Vec<String> = axes
+        .iter()
+        .map(|axis| {
+            let preferred: Vec<String> = axis
+                .preferred
+                .iter()
+                .map(|(value, q)| format!("{{\"value\":{},\"q\":{}}}", json_string(value), q))
+                .collect();
+            format!(
+                "{}:{{\"value\":{},\"selected\":{},\"preferred\":[{}]}}",
+                json_string(axis.header),
+                json_option(&axis.value),
+                json_option(&axis.selected),
+                preferred.join(",")
+            )
+        })
+        .collect();
+
+    println!(
+        "{{\"axes\":{{{}}},\"vary\":{}}}",
+        axes.join(","),
+        json_string(&vary)
+    );
+}
+
+fn json_option(value: &Option<String>) -> String {
+    match value {
+        Some(value) => json_string(value),
+        None => "null".to_string(),
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
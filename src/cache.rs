@@ -0,0 +1,134 @@
+//! Caching negotiation results for repeated header values.
+//!
+//! Most requests send one of a few header values, so a [`CachedNegotiator`]
+//! negotiates each value once and returns the same result for it after
+//! that. Results are the positions of the preferred values in the provided
+//! list, as from [`charset::preferred_indices`], and are shared rather than
+//! copied on a hit.
+//!
+//! The cache holds at most its capacity of header values, dropping the
+//! least recently used one to make room. Headers longer than the default
+//! [`max_header_len`](Limits::max_header_len) are negotiated but never
+//! cached, so a client can't fill the cache with huge keys.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::options::Limits;
+use crate::{charset, encoding};
+
+/// Negotiates one axis against a fixed provided list, caching results by header value.
+///
+/// It can be shared between threads, which take turns to use the cache but
+/// negotiate a missed header value without holding it.
+#[derive(Debug)]
+pub struct CachedNegotiator {
+    provided: Vec<String>,
+    negotiate: fn(Option<&str>, &[&str]) -> Vec<usize>,
+    missing: Arc<[usize]>,
+    capacity: usize,
+    cache: Mutex<Lru>,
+}
+
+/// Cached results, each with when it was last used.
+#[derive(Debug, Default)]
+struct Lru {
+    entries: HashMap<String, (Arc<[usize]>, u64)>,
+    clock: u64,
+}
+
+impl CachedNegotiator {
+    /// Negotiate Accept-Charset headers against `provided`, caching up to `capacity` values.
+    pub fn charsets(provided: &[&str], capacity: usize) -> Self {
+        Self::new(provided, capacity, charset::preferred_indices)
+    }
+
+    /// Negotiate Accept-Encoding headers against `provided`, caching up to `capacity` values.
+    pub fn encodings(provided: &[&str], capacity: usize) -> Self {
+        Self::new(provided, capacity, encoding::preferred_indices)
+    }
+
+    fn new(
+        provided: &[&str],
+        capacity: usize,
+        negotiate: fn(Option<&str>, &[&str]) -> Vec<usize>,
+    ) -> Self {
+        Self {
+            provided: provided.iter().map(|value| value.to_string()).collect(),
+            negotiate,
+            // A missing header always negotiates the same, so it is kept aside.
+            missing: negotiate(None, provided).into(),
+            capacity,
+            cache: Mutex::new(Lru::default()),
+        }
+    }
+
+    /// The provided values results are positions in.
+    pub fn provided(&self) -> &[String] {
+        &self.provided
+    }
+
+    /// The most header values cached at once.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of header values cached.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Whether no header values are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the positions in the provided list of the preferred values, most preferred first.
+    pub fn preferred(&self, accept: Option<&str>) -> Arc<[usize]> {
+        let accept = match accept {
+            Some(accept) => accept,
+            None => return self.missing.clone(),
+        };
+        if let Some(indices) = self.lock().get(accept) {
+            return indices;
+        }
+
+        let provided: Vec<&str> = self.provided.iter().map(String::as_str).collect();
+        let indices: Arc<[usize]> = (self.negotiate)(Some(accept), &provided).into();
+        if self.capacity > 0 && accept.len() <= Limits::DEFAULT.max_header_len {
+            self.lock().insert(accept, indices.clone(), self.capacity);
+        }
+        indices
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Lru> {
+        // The cache is left consistent even if a thread panicked holding it.
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Lru {
+    fn get(&mut self, accept: &str) -> Option<Arc<[usize]>> {
+        self.clock += 1;
+        let (indices, used) = self.entries.get_mut(accept)?;
+        *used = self.clock;
+        Some(indices.clone())
+    }
+
+    fn insert(&mut self, accept: &str, indices: Arc<[usize]>, capacity: usize) {
+        // Another thread may have cached it while this one negotiated.
+        if !self.entries.contains_key(accept) && self.entries.len() >= capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, &(_, used))| used)
+                .map(|(accept, _)| accept.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.entries
+            .insert(accept.to_string(), (indices, self.clock));
+    }
+}
@@ -0,0 +1,515 @@
+//
+// negotiator
+// Copyright(c) 2012 Isaac Z. Schlueter
+// Copyright(c) 2014 Federico Romero
+// Copyright(c) 2014-2015 Douglas Christopher Wilson
+// Copyright(c) 2020 Jeremiah Senkpiel
+// MIT Licensed
+//
+
+//! Accept-Charset negotiation.
+//!
+//! A missing header means any charset is acceptable, and `*` matches any
+//! charset not listed on its own.
+//!
+//! Charsets are ordered by quality, then exact matches come before `*`,
+//! then by the position of the matching entry in the header, and finally
+//! by their position in the provided list, so equally preferred charsets
+//! always come out in the same order.
+
+use std::fmt;
+
+use crate::error::NegotiationError;
+use crate::header_source::{combined_header, HeaderSource};
+use crate::options::{HeaderKind, NegotiationOptions};
+use crate::tokens::{self, AcceptToken, BatchSummary, TokenOptions};
+
+/// Accept-Charset allows `*` to match any charset.
+const OPTIONS: TokenOptions = TokenOptions {
+    wildcard: true,
+    kind: HeaderKind::AcceptCharset,
+    negotiation: NegotiationOptions::DEFAULT,
+};
+
+/// A parsed Accept-Charset header, which can be negotiated against repeatedly.
+///
+/// With the `headers` feature this implements `headers::Header`, so it can be
+/// read with `HeaderMapExt::typed_get` or extracted as a `TypedHeader`.
+#[derive(Clone, Debug, Default)]
+pub struct AcceptCharsetHeader {
+    entries: Vec<AcceptToken<'static>>,
+    options: NegotiationOptions,
+}
+
+impl AcceptCharsetHeader {
+    /// Parse an Accept-Charset header.
+    pub fn parse(accept: &str) -> Self {
+        Self {
+            entries: parse(accept)
+                .into_iter()
+                .map(AcceptToken::into_owned)
+                .collect(),
+            options: NegotiationOptions::default(),
+        }
+    }
+
+    /// Use these options when negotiating against this header.
+    pub fn with_options(mut self, options: NegotiationOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The entries of the header, in header order.
+    pub fn entries(&self) -> &[AcceptToken<'static>] {
+        &self.entries
+    }
+
+    /// Get the preferred charsets from this header.
+    pub fn preferred(&self, provided: &[&str]) -> Vec<String> {
+        self.preferred_with_quality(provided)
+            .into_iter()
+            .map(|(charset, _)| charset)
+            .collect()
+    }
+
+    /// Get the preferred charsets from this header along with their quality.
+    pub fn preferred_with_quality(&self, provided: &[&str]) -> Vec<(String, f32)> {
+        let options = TokenOptions {
+            negotiation: self.options.clone(),
+            ..OPTIONS
+        };
+
+        tokens::preferred_parsed_with_quality(&self.entries, provided, options.wildcard, &options)
+    }
+}
+
+impl fmt::Display for AcceptCharsetHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a charset was chosen, from [`explain`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CharsetExplanation {
+    /// The chosen charset, as provided.
+    pub charset: String,
+    /// The header entry that matched the charset, as written.
+    pub entry: String,
+    /// The position of the entry among the header's comma-separated elements.
+    pub index: usize,
+    /// Whether the entry names the charset, rather than being `*`.
+    pub exact: bool,
+    /// The quality of the entry, from 0.0 to 1.0.
+    pub quality: f32,
+}
+
+/// The outcome of negotiating a charset, from [`negotiate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CharsetOutcome {
+    /// Nothing was provided, and the client accepts any charset.
+    ///
+    /// The server should use its own default. It can check that default with
+    /// [`is_acceptable`], in case the header refuses it specifically.
+    AnyAcceptable,
+    /// The most preferred of the provided charsets.
+    Matched(String),
+    /// None of the provided charsets are acceptable, e.g. a 406 Not Acceptable.
+    NotAcceptable,
+}
+
+impl CharsetOutcome {
+    /// Get the matched charset, if there is one.
+    pub fn into_matched(self) -> Option<String> {
+        match self {
+            CharsetOutcome::Matched(charset) => Some(charset),
+            _ => None,
+        }
+    }
+}
+
+/// Registered charset names and their aliases, from the IANA character set
+/// registry, along with a few common unregistered spellings.
+const ALIASES: &[(&str, &[&str])] = &[
+    ("UTF-8", &["csUTF8", "utf8"]),
+    ("UTF-16", &["csUTF16", "utf16"]),
+    (
+        "US-ASCII",
+        &[
+            "iso-ir-6",
+            "ANSI_X3.4-1968",
+            "ANSI_X3.4-1986",
+            "ISO_646.irv:1991",
+            "ISO646-US",
+            "us",
+            "IBM367",
+            "cp367",
+            "csASCII",
+            "ascii",
+        ],
+    ),
+    (
+        "ISO-8859-1",
+        &[
+            "iso-ir-100",
+            "ISO_8859-1",
+            "ISO_8859-1:1987",
+            "latin1",
+            "l1",
+            "IBM819",
+            "CP819",
+            "csISOLatin1",
+            "iso8859-1",
+        ],
+    ),
+    (
+        "ISO-8859-15",
+        &["ISO_8859-15", "Latin-9", "csISO885915", "latin9"],
+    ),
+    ("windows-1250", &["cswindows1250", "cp1250"]),
+    ("windows-1251", &["cswindows1251", "cp1251"]),
+    ("windows-1252", &["cswindows1252", "cp1252"]),
+    ("windows-1253", &["cswindows1253", "cp1253"]),
+    ("windows-1254", &["cswindows1254", "cp1254"]),
+    ("windows-1255", &["cswindows1255", "cp1255"]),
+    ("windows-1256", &["cswindows1256", "cp1256"]),
+    ("windows-1257", &["cswindows1257", "cp1257"]),
+    ("windows-1258", &["cswindows1258", "cp1258"]),
+];
+
+/// Get the registered name of a charset, if it is one of the known names or aliases.
+///
+/// Names are compared ASCII case-insensitively.
+pub fn canonical_name(charset: &str) -> Option<&'static str> {
+    ALIASES
+        .iter()
+        .find(|(name, aliases)| {
+            name.eq_ignore_ascii_case(charset)
+                || aliases
+                    .iter()
+                    .any(|alias| alias.eq_ignore_ascii_case(charset))
+        })
+        .map(|&(name, _)| name)
+}
+
+/// Get the header to negotiate with.
+fn header_or_any(accept: Option<&str>) -> Option<&str> {
+    // RFC 2616 sec 14.2: no header = *
+    Some(accept.unwrap_or("*"))
+}
+
+/// Parse an Accept-Charset header.
+pub fn parse(accept: &str) -> Vec<AcceptToken<'_>> {
+    tokens::parse_tokens(accept)
+}
+
+/// Get the preferred charsets from an already parsed Accept-Charset header.
+///
+/// Parse a header once with [`parse`] to negotiate against it repeatedly.
+pub fn preferred_from_parsed(parsed: &[AcceptToken<'_>], provided: &[&str]) -> Vec<String> {
+    tokens::preferred_parsed_with_quality(parsed, provided, OPTIONS.wildcard, &OPTIONS)
+        .into_iter()
+        .map(|(charset, _)| charset)
+        .collect()
+}
+
+/// Get the charsets an Accept-Charset header accepts, most preferred first.
+///
+/// A missing header lists `*`.
+pub fn acceptable(accept: Option<&str>) -> Vec<String> {
+    acceptable_with_options(accept, &NegotiationOptions::default())
+}
+
+/// Get the charsets an Accept-Charset header accepts, with options.
+pub fn acceptable_with_options(accept: Option<&str>, options: &NegotiationOptions) -> Vec<String> {
+    let options = TokenOptions {
+        negotiation: options.clone(),
+        ..OPTIONS
+    };
+
+    tokens::acceptable_tokens(header_or_any(accept), &options)
+}
+
+/// Get the preferred charsets from an Accept-Charset header.
+pub fn preferred(accept: Option<&str>, provided: &[&str]) -> Vec<String> {
+    preferred_ref(accept, provided)
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Get the preferred charsets from an Accept-Charset header, borrowed from `provided`.
+pub fn preferred_ref<'a>(accept: Option<&str>, provided: &[&'a str]) -> Vec<&'a str> {
+    preferred_indices(accept, provided)
+        .into_iter()
+        .map(|index| provided[index])
+        .collect()
+}
+
+/// Get the preferred charsets from an Accept-Charset header, with options.
+pub fn preferred_with_options(
+    accept: Option<&str>,
+    provided: &[&str],
+    options: &NegotiationOptions,
+) -> Vec<String> {
+    let options = TokenOptions {
+        negotiation: options.clone(),
+        ..OPTIONS
+    };
+
+    tokens::preferred_tokens_with_options(header_or_any(accept), provided, &options)
+}
+
+/// Get the preferred charsets from an Accept-Charset header, assuming a default when it is missing.
+///
+/// The default is negotiated as if it were the header, e.g. `"UTF-8, *;q=0.1"`
+/// to prefer UTF-8 from clients that don't say, instead of accepting any charset.
+pub fn preferred_with_default(
+    accept: Option<&str>,
+    provided: &[&str],
+    default: &str,
+) -> Vec<String> {
+    preferred(Some(accept.unwrap_or(default)), provided)
+}
+
+/// Get the preferred charsets from an Accept-Charset header, ignoring entries below `min_quality`.
+///
+/// This includes a wildcard, so `"UTF-8, *;q=0.01"` with a `min_quality` of 0.1
+/// only accepts UTF-8. An entry exactly at `min_quality` is still acceptable.
+pub fn preferred_with_min_quality(
+    accept: Option<&str>,
+    provided: &[&str],
+    min_quality: f32,
+) -> Vec<String> {
+    let options = NegotiationOptions {
+        min_quality,
+        ..NegotiationOptions::DEFAULT
+    };
+
+    preferred_with_options(accept, provided, &options)
+}
+
+/// Get the preferred charsets from an Accept-Charset header, matching aliases of the same charset.
+///
+/// Both the header and the provided charsets are compared by their
+/// [`canonical_name`], so `utf8` in the header matches a provided `UTF-8`.
+/// The provided spelling is returned.
+pub fn preferred_with_aliases(accept: Option<&str>, provided: &[&str]) -> Vec<String> {
+    preferred_with_aliases_and_options(accept, provided, &NegotiationOptions::DEFAULT)
+}
+
+/// Get the preferred charsets from an Accept-Charset header, matching aliases, with options.
+///
+/// `on_unmatched` is given the charsets as the header spells them.
+pub fn preferred_with_aliases_and_options(
+    accept: Option<&str>,
+    provided: &[&str],
+    options: &NegotiationOptions,
+) -> Vec<String> {
+    let options = TokenOptions {
+        negotiation: options.clone(),
+        ..OPTIONS
+    };
+
+    tokens::preferred_canonical_indices(header_or_any(accept), provided, &options, canonical_name)
+        .into_iter()
+        .map(|index| provided[index].to_string())
+        .collect()
+}
+
+/// Get the preferred charsets from an Accept-Charset header, with ISO-8859-1 implicitly acceptable.
+///
+/// RFC 2616 made ISO-8859-1 acceptable at q=1 to every client sending a
+/// header without `*` or ISO-8859-1 in it, but RFC 9110 dropped the rule.
+/// Some legacy clients still rely on it.
+pub fn preferred_with_implicit_latin1(accept: Option<&str>, provided: &[&str]) -> Vec<String> {
+    let accept = match accept {
+        Some(accept) => accept,
+        None => return preferred(accept, provided),
+    };
+
+    // Added after parsing, so it doesn't count toward the limits.
+    let mut accepts = tokens::parse_tokens_within(accept, &OPTIONS.negotiation.limits);
+    if !mentions_latin1(&accepts) {
+        tokens::imply_token_with_quality(&mut accepts, LATIN1, OPTIONS.wildcard, 1.0);
+    }
+
+    preferred_from_parsed(&accepts, provided)
+}
+
+/// The charset RFC 2616 made implicitly acceptable.
+const LATIN1: &str = "ISO-8859-1";
+
+/// Check if a header has a `*` or ISO-8859-1 entry, under any of its names and with any quality.
+fn mentions_latin1(accepts: &[AcceptToken<'_>]) -> bool {
+    accepts
+        .iter()
+        .any(|entry| entry.token() == "*" || canonical_name(entry.token()) == Some(LATIN1))
+}
+
+/// Get the preferred charsets from an Accept-Charset header, weighted by server preference.
+///
+/// See [`preferred_tokens_weighted`](crate::preferred_tokens_weighted).
+pub fn preferred_weighted(accept: Option<&str>, provided: &[(&str, f32)]) -> Vec<String> {
+    tokens::preferred_tokens_weighted(header_or_any(accept), provided, &OPTIONS)
+}
+
+/// Get the preferred charsets from an Accept-Charset header, failing if it exceeds the limits.
+pub fn try_preferred_with_options(
+    accept: Option<&str>,
+    provided: &[&str],
+    options: &NegotiationOptions,
+) -> Result<Vec<String>, NegotiationError> {
+    let options = TokenOptions {
+        negotiation: options.clone(),
+        ..OPTIONS
+    };
+
+    tokens::try_preferred_tokens_with_options(header_or_any(accept), provided, &options)
+}
+
+/// Get the most preferred charset from an Accept-Charset header.
+pub fn most_preferred(accept: Option<&str>, provided: &[&str]) -> Option<String> {
+    most_preferred_ref(accept, provided).map(str::to_string)
+}
+
+/// Get the most preferred charset from an Accept-Charset header, borrowed from `provided`.
+pub fn most_preferred_ref<'a>(accept: Option<&str>, provided: &[&'a str]) -> Option<&'a str> {
+    most_preferred_index(accept, provided).map(|index| provided[index])
+}
+
+/// Get the position in `provided` of the most preferred charset.
+pub fn most_preferred_index(accept: Option<&str>, provided: &[&str]) -> Option<usize> {
+    tokens::most_preferred_token_index(header_or_any(accept), provided, &OPTIONS)
+}
+
+/// Get the first provided charset the header accepts, in the server's order.
+///
+/// The client's qualities only decide what is acceptable, so with
+/// `"ISO-8859-1, UTF-8;q=0.5"` a provided `["UTF-8", "ISO-8859-1"]` gives UTF-8.
+pub fn server_preferred(accept: Option<&str>, provided: &[&str]) -> Option<String> {
+    preferred_indices(accept, provided)
+        .into_iter()
+        .min()
+        .map(|index| provided[index].to_string())
+}
+
+/// Explain which charset is most preferred, and which header entry made it so.
+///
+/// A missing header is explained as `*`. Like [`most_preferred`], nothing is
+/// chosen when `provided` is empty.
+pub fn explain(accept: Option<&str>, provided: &[&str]) -> Option<CharsetExplanation> {
+    let (index, entry, priority) =
+        tokens::explain_token(header_or_any(accept), provided, &OPTIONS)?;
+
+    Some(CharsetExplanation {
+        charset: provided[index].to_string(),
+        entry: entry.to_string(),
+        index: priority.entry_index()?,
+        exact: priority.is_exact(),
+        quality: priority.quality(),
+    })
+}
+
+/// Negotiate a charset, telling a missing choice apart from an unacceptable one.
+///
+/// Unlike [`most_preferred`], an empty `provided` list gives
+/// [`CharsetOutcome::AnyAcceptable`] when the header accepts `*`.
+pub fn negotiate(accept: Option<&str>, provided: &[&str]) -> CharsetOutcome {
+    if provided.is_empty() {
+        if acceptable(accept).iter().any(|charset| charset == "*") {
+            return CharsetOutcome::AnyAcceptable;
+        }
+        return CharsetOutcome::NotAcceptable;
+    }
+
+    match most_preferred(accept, provided) {
+        Some(charset) => CharsetOutcome::Matched(charset),
+        None => CharsetOutcome::NotAcceptable,
+    }
+}
+
+/// Get the positions in `provided` of the preferred charsets, most preferred first.
+pub fn preferred_indices(accept: Option<&str>, provided: &[&str]) -> Vec<usize> {
+    tokens::preferred_token_indices(header_or_any(accept), provided, &OPTIONS)
+}
+
+/// Get the preferred charsets from an Accept-Charset header along with their quality.
+pub fn preferred_with_quality(accept: Option<&str>, provided: &[&str]) -> Vec<(String, f32)> {
+    tokens::preferred_tokens_with_quality(header_or_any(accept), provided, &OPTIONS)
+}
+
+/// Get the most preferred charset which also passes the predicate.
+///
+/// The predicate is only called on acceptable charsets, in preference order,
+/// and no more charsets are checked once one passes.
+pub fn preferred_where(
+    accept: Option<&str>,
+    provided: &[&str],
+    f: impl Fn(&str) -> bool,
+) -> Option<String> {
+    tokens::preferred_token_where(header_or_any(accept), provided, &OPTIONS, f)
+}
+
+/// Get the preferred charsets from the Accept-Charset headers of a request.
+pub fn preferred_from_headers(
+    headers: &(impl HeaderSource + ?Sized),
+    provided: &[&str],
+) -> Vec<String> {
+    preferred(
+        combined_header(headers, "Accept-Charset").as_deref(),
+        provided,
+    )
+}
+
+/// Check if a charset is acceptable according to an Accept-Charset header.
+///
+/// The header is only read until the charset is found with a non-zero quality.
+pub fn is_acceptable(accept: Option<&str>, charset: &str) -> bool {
+    tokens::is_token_acceptable(header_or_any(accept), charset, &OPTIONS)
+}
+
+/// Get the provided charsets an Accept-Charset header refuses with `q=0`.
+///
+/// See [`rejected_tokens`](crate::rejected_tokens).
+pub fn rejected(accept: Option<&str>, provided: &[&str]) -> Vec<String> {
+    tokens::rejected_tokens(accept, provided, &OPTIONS)
+}
+
+/// Get the [`encoding_rs`] encoding for the most preferred charset it knows.
+///
+/// Provided charsets whose label encoding_rs doesn't recognize are skipped.
+/// With no provided charsets the header's own preference is used, where `*`
+/// means UTF-8.
+#[cfg(feature = "encoding_rs")]
+pub fn encoding(accept: Option<&str>, provided: &[&str]) -> Option<&'static encoding_rs::Encoding> {
+    let for_label = |label: &str| encoding_rs::Encoding::for_label(label.as_bytes());
+    if provided.is_empty() {
+        return acceptable(accept)
+            .iter()
+            .find_map(|charset| match charset.as_str() {
+                "*" if is_acceptable(accept, "UTF-8") => Some(encoding_rs::UTF_8),
+                charset => for_label(charset),
+            });
+    }
+
+    preferred_ref(accept, provided)
+        .into_iter()
+        .find_map(for_label)
+}
+
+/// Negotiate many Accept-Charset headers against the same provided charsets.
+pub fn batch<'a>(
+    accepts: impl IntoIterator<Item = Option<&'a str>>,
+    provided: &[&str],
+) -> BatchSummary {
+    let accepts = accepts.into_iter().map(header_or_any);
+
+    tokens::preferred_token_batch(accepts, provided, &OPTIONS)
+}
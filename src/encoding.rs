@@ -0,0 +1,512 @@
+//
+// negotiator
+// Copyright(c) 2012 Isaac Z. Schlueter
+// Copyright(c) 2014 Federico Romero
+// Copyright(c) 2014-2015 Douglas Christopher Wilson
+// Copyright(c) 2020 Jeremiah Senkpiel
+// MIT Licensed
+//
+
+//! Accept-Encoding negotiation.
+//!
+//! `*` matches any coding not listed on its own. The `identity` coding is
+//! acceptable unless the header excludes it, by name or through `*`, as
+//! RFC 9110 section 12.5.3 requires. A missing header accepts any coding,
+//! unless [`MissingHeader::IdentityOnly`] is chosen.
+//!
+//! When the header does not mention `identity`, it is negotiated as if the
+//! header ended with it, at the lowest quality the header gives any coding.
+//! The codings the client names are then preferred to it.
+//!
+//! Codings are ordered by quality, then exact matches come before `*` and
+//! the implied `identity`, and then by their position in the provided list,
+//! unlike charsets, where the position of the matching header entry comes
+//! first. `"gzip, deflate, br"` leaves the choice between the three to the
+//! server.
+//!
+//! With a [`min_quality`](NegotiationOptions::min_quality), codings below
+//! it are not acceptable, but if no provided coding clears it, `identity`
+//! still is unless the header excludes it.
+//!
+//! `x-gzip` and `x-compress` match `gzip` and `compress`, whichever
+//! spelling the header and the provided codings use.
+//!
+//! Only the first [`max_entries`](Limits::max_entries) entries of a header
+//! are read, 64 by default, so a header of junk codings costs no more than
+//! a full one. [`try_preferred_with_options`] fails on the rest instead.
+
+use crate::error::NegotiationError;
+use crate::options::{HeaderKind, Limits, NegotiationOptions};
+use crate::tokens::{self, AcceptToken, Specificity, TokenOptions};
+
+/// Accept-Encoding allows `*` to match any coding.
+const OPTIONS: TokenOptions = TokenOptions {
+    wildcard: true,
+    kind: HeaderKind::AcceptEncoding,
+    negotiation: NegotiationOptions::DEFAULT,
+};
+
+/// The coding which leaves the content as it is.
+const IDENTITY: &str = "identity";
+
+/// What a missing Accept-Encoding header means.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingHeader {
+    /// Any coding is acceptable, as RFC 9110 says, so the provided order decides.
+    #[default]
+    AnyCoding,
+    /// Only `identity` is acceptable, so nothing is encoded unless the client asks.
+    IdentityOnly,
+}
+
+/// Options for Accept-Encoding negotiation.
+#[derive(Clone, Debug, Default)]
+pub struct EncodingOptions {
+    /// What a missing header means.
+    pub missing_header: MissingHeader,
+    /// Options shared with the other negotiation axes.
+    pub negotiation: NegotiationOptions,
+}
+
+/// What matched a negotiated coding, from [`negotiate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchSource {
+    /// An entry naming the coding, or one of its aliases.
+    Exact,
+    /// The `*` entry.
+    Wildcard,
+    /// The `identity` implied by a header which doesn't mention it.
+    ImplicitIdentity,
+}
+
+/// A negotiated coding, with what matched it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncodingMatch {
+    /// The coding, as provided.
+    pub coding: String,
+    /// The quality of the matching entry, from 0.0 to 1.0.
+    pub quality: f32,
+    /// What matched the coding.
+    pub source: MatchSource,
+}
+
+/// Codings and the older names RFC 9110 says to treat as the same.
+const ALIASES: &[(&str, &str)] = &[("gzip", "x-gzip"), ("compress", "x-compress")];
+
+/// Get the name of a coding, if it is `gzip`, `compress`, or one of their `x-` aliases.
+///
+/// Names are compared ASCII case-insensitively.
+pub fn canonical_name(coding: &str) -> Option<&'static str> {
+    ALIASES
+        .iter()
+        .find(|(name, alias)| {
+            name.eq_ignore_ascii_case(coding) || alias.eq_ignore_ascii_case(coding)
+        })
+        .map(|&(name, _)| name)
+}
+
+/// Get the header to negotiate with.
+fn header_or_default(accept: Option<&str>, missing: MissingHeader) -> &str {
+    match (accept, missing) {
+        (Some(accept), _) => accept,
+        (None, MissingHeader::AnyCoding) => "*",
+        // Leaves only the implied identity.
+        (None, MissingHeader::IdentityOnly) => "",
+    }
+}
+
+/// Parse an Accept-Encoding header.
+///
+/// Headers of bare codings, such as `"gzip, deflate, br"`, are split without
+/// the general parser, which gives the same entries.
+pub fn parse(accept: &str) -> Vec<AcceptToken<'_>> {
+    parse_within(accept, &OPTIONS.negotiation.limits)
+}
+
+/// Parse an Accept-Encoding header, ignoring whatever is beyond the limits.
+fn parse_within<'a>(accept: &'a str, limits: &Limits) -> Vec<AcceptToken<'a>> {
+    tokens::parse_bare_tokens(accept, limits)
+        .unwrap_or_else(|| tokens::parse_tokens_within(accept, limits))
+}
+
+/// Add `identity` to the parsed entries of a header, if the header doesn't mention it.
+fn with_identity(mut accepts: Vec<AcceptToken<'_>>) -> (Vec<AcceptToken<'_>>, bool) {
+    let implied = tokens::imply_token(&mut accepts, IDENTITY, OPTIONS.wildcard);
+    (accepts, implied)
+}
+
+/// Get the codings an Accept-Encoding header accepts, most preferred first.
+///
+/// This lists the header, including an implied `identity`.
+pub fn acceptable(accept: Option<&str>) -> Vec<String> {
+    let accept = header_or_default(accept, MissingHeader::default());
+
+    let (accepts, _) = with_identity(parse_within(accept, &OPTIONS.negotiation.limits));
+
+    tokens::acceptable_parsed_with_quality(&accepts, &OPTIONS)
+        .into_iter()
+        .map(|(encoding, _)| encoding)
+        .collect()
+}
+
+/// Get the preferred codings from an Accept-Encoding header.
+///
+/// Codings are matched by their [`canonical_name`], and the provided spelling
+/// is returned.
+pub fn preferred(accept: Option<&str>, provided: &[&str]) -> Vec<String> {
+    preferred_with_options(accept, provided, &EncodingOptions::default())
+}
+
+/// Get the preferred codings from an Accept-Encoding header, with options.
+pub fn preferred_with_options(
+    accept: Option<&str>,
+    provided: &[&str],
+    options: &EncodingOptions,
+) -> Vec<String> {
+    matches(accept, provided, options)
+        .into_iter()
+        .map(|matched| matched.coding)
+        .collect()
+}
+
+/// Get the preferred codings from an Accept-Encoding header, failing if it is malformed.
+///
+/// The header must follow the grammar of RFC 9110 and stay within the limits.
+/// [`preferred_with_options`] skips the entries this fails on.
+pub fn try_preferred_with_options(
+    accept: Option<&str>,
+    provided: &[&str],
+    options: &EncodingOptions,
+) -> Result<Vec<String>, NegotiationError> {
+    let accept = header_or_default(accept, options.missing_header);
+    let accepts = tokens::try_parse_tokens_within(accept, &options.negotiation.limits)?;
+    let (priorities, _) = parsed_priorities(accepts, provided, options);
+
+    Ok(priorities
+        .into_iter()
+        .map(|(index, _)| options.negotiation.output(provided[index]))
+        .collect())
+}
+
+/// Get the preferred codings from an Accept-Encoding header along with their quality.
+///
+/// The quality is that of the entry which matched the coding, which may be
+/// `*` or the implied `identity`.
+pub fn preferred_with_quality(accept: Option<&str>, provided: &[&str]) -> Vec<(String, f32)> {
+    matches(accept, provided, &EncodingOptions::default())
+        .into_iter()
+        .map(|matched| (matched.coding, matched.quality))
+        .collect()
+}
+
+/// Get the positions in `provided` of the preferred codings, most preferred first.
+pub fn preferred_indices(accept: Option<&str>, provided: &[&str]) -> Vec<usize> {
+    let (priorities, _) = priorities(accept, provided, &EncodingOptions::default());
+    priorities.into_iter().map(|(index, _)| index).collect()
+}
+
+/// Negotiate the most preferred coding, and tell what in the header matched it.
+///
+/// Like [`most_preferred`], `None` means no provided coding is acceptable.
+pub fn negotiate(accept: Option<&str>, provided: &[&str]) -> Option<EncodingMatch> {
+    matches(accept, provided, &EncodingOptions::default())
+        .into_iter()
+        .next()
+}
+
+/// Get the acceptable codings, most preferred first, with what matched them.
+fn matches(
+    accept: Option<&str>,
+    provided: &[&str],
+    options: &EncodingOptions,
+) -> Vec<EncodingMatch> {
+    let (priorities, implied) = priorities(accept, provided, options);
+
+    priorities
+        .into_iter()
+        .map(|(index, priority)| EncodingMatch {
+            coding: options.negotiation.output(provided[index]),
+            quality: priority.quality(),
+            // identity is only implied when there is no `*` to match instead.
+            source: match (priority.is_exact(), implied) {
+                (true, _) => MatchSource::Exact,
+                (false, true) => MatchSource::ImplicitIdentity,
+                (false, false) => MatchSource::Wildcard,
+            },
+        })
+        .collect()
+}
+
+/// Get the indices of the acceptable codings, most preferred first, with their priorities.
+///
+/// Also tells if the header had `identity` implied.
+fn priorities(
+    accept: Option<&str>,
+    provided: &[&str],
+    options: &EncodingOptions,
+) -> (Vec<(usize, Specificity)>, bool) {
+    let accept = header_or_default(accept, options.missing_header);
+    parsed_priorities(
+        parse_within(accept, &options.negotiation.limits),
+        provided,
+        options,
+    )
+}
+
+/// Get the indices of the acceptable codings from a parsed header.
+fn parsed_priorities(
+    accepts: Vec<AcceptToken<'_>>,
+    provided: &[&str],
+    options: &EncodingOptions,
+) -> (Vec<(usize, Specificity)>, bool) {
+    let (mut accepts, implied) = with_identity(accepts);
+    tokens::canonicalize_tokens(&mut accepts, canonical_name);
+    let names: Vec<&str> = provided
+        .iter()
+        .map(|&coding| canonical_name(coding).unwrap_or(coding))
+        .collect();
+
+    let options = TokenOptions {
+        negotiation: options.negotiation.clone(),
+        ..OPTIONS
+    };
+
+    let mut priorities = tokens::preferred_parsed_priorities(
+        &accepts,
+        &names,
+        options.wildcard,
+        &options,
+        tokens::compare_provided_order,
+    );
+    if priorities.is_empty() && options.negotiation.min_quality > 0.0 {
+        // Nothing clears the bar, but identity is still better than a 406.
+        priorities = names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(IDENTITY))
+            .and_then(|index| Some((index, tokens::token_priority(&accepts, IDENTITY, &options)?)))
+            .filter(|(_, priority)| priority.quality() > 0.0)
+            .into_iter()
+            .collect();
+    }
+    (priorities, implied)
+}
+
+/// Get the preferred codings from an Accept-Encoding header, weighted by server preference.
+///
+/// Each provided coding comes with a server quality from 0.0 to 1.0, which
+/// is multiplied with the quality the header gives it before sorting. Ties
+/// go to exact matches, then to the provided order, and a server quality of
+/// 0.0 makes a coding unacceptable.
+pub fn preferred_weighted(accept: Option<&str>, provided: &[(&str, f32)]) -> Vec<String> {
+    let codings: Vec<&str> = provided.iter().map(|&(coding, _)| coding).collect();
+    let (priorities, _) = priorities(accept, &codings, &EncodingOptions::default());
+
+    tokens::weigh_priorities(priorities, |index| provided[index].1)
+        .into_iter()
+        .map(|(index, _)| OPTIONS.negotiation.output(codings[index]))
+        .collect()
+}
+
+/// Get the most preferred coding from an Accept-Encoding header.
+///
+/// `None` means the header excludes every provided coding, `identity`
+/// included, so no response body is acceptable: a 406 Not Acceptable.
+pub fn most_preferred(accept: Option<&str>, provided: &[&str]) -> Option<String> {
+    negotiate(accept, provided).map(|matched| matched.coding)
+}
+
+/// Parse an Accept-Encoding header for matching single codings, with `identity` and aliases.
+fn parse_canonical(accept: Option<&str>) -> Vec<AcceptToken<'_>> {
+    let accept = header_or_default(accept, MissingHeader::default());
+    let (mut accepts, _) = with_identity(parse_within(accept, &OPTIONS.negotiation.limits));
+    tokens::canonicalize_tokens(&mut accepts, canonical_name);
+    accepts
+}
+
+/// Get the quality the entry best matching a coding gives it, if any entry matches it.
+fn coding_quality(accepts: &[AcceptToken<'_>], coding: &str) -> Option<f32> {
+    let name = canonical_name(coding).unwrap_or(coding);
+    tokens::token_priority(accepts, name, &OPTIONS).map(|priority| priority.quality())
+}
+
+/// Get the most preferred chain of codings, each applied on top of the one before.
+///
+/// A chain is acceptable if every coding in it is, and is as preferred as
+/// the least preferred of them. An empty chain applies no coding, so it is
+/// negotiated as `identity`. Ties go to the chain listed first.
+pub fn preferred_chain<'a>(
+    accept: Option<&str>,
+    chains: &[&'a [&'a str]],
+) -> Option<&'a [&'a str]> {
+    let accepts = parse_canonical(accept);
+    let quality = |coding: &str| coding_quality(&accepts, coding).unwrap_or(0.0);
+
+    let mut best: Option<(f32, &'a [&'a str])> = None;
+    for &chain in chains {
+        let q = if chain.is_empty() {
+            quality(IDENTITY)
+        } else {
+            chain
+                .iter()
+                .map(|&coding| quality(coding))
+                .fold(1.0, f32::min)
+        };
+        if q > 0.0 && !matches!(best, Some((best_q, _)) if best_q >= q) {
+            best = Some((q, chain));
+        }
+    }
+    best.map(|(_, chain)| chain)
+}
+
+/// Check if a coding would be selected were it the only one provided.
+///
+/// Reading the header stops at the first exact match with a non-zero quality,
+/// and nothing is allocated for it.
+pub fn is_acceptable(accept: Option<&str>, coding: &str) -> bool {
+    let accept = header_or_default(accept, MissingHeader::default());
+
+    tokens::is_canonical_token_acceptable(
+        accept,
+        OPTIONS.wildcard,
+        coding,
+        &OPTIONS,
+        canonical_name,
+        Some(IDENTITY),
+    )
+}
+
+/// A precompressed variant of a static file, such as `index.html.br`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PrecompressedKind {
+    /// `br`, in a `.br` file.
+    Brotli,
+    /// `gzip`, in a `.gz` file.
+    Gzip,
+    /// `zstd`, in a `.zst` file.
+    Zstd,
+    /// The file itself, with no coding.
+    Identity,
+}
+
+impl PrecompressedKind {
+    /// The coding of the variant, as Content-Encoding names it.
+    pub fn coding(self) -> &'static str {
+        match self {
+            PrecompressedKind::Brotli => "br",
+            PrecompressedKind::Gzip => "gzip",
+            PrecompressedKind::Zstd => "zstd",
+            PrecompressedKind::Identity => IDENTITY,
+        }
+    }
+
+    /// The extension the variant's file adds to the file name, which is empty for `Identity`.
+    pub fn extension(self) -> &'static str {
+        match self {
+            PrecompressedKind::Brotli => ".br",
+            PrecompressedKind::Gzip => ".gz",
+            PrecompressedKind::Zstd => ".zst",
+            PrecompressedKind::Identity => "",
+        }
+    }
+}
+
+/// Get the file extension of the most preferred precompressed variant.
+///
+/// `available` lists the variants on disk, the server's favourite first,
+/// which decides between equally preferred codings. List
+/// [`PrecompressedKind::Identity`] if the file itself can be sent. `None`
+/// means no variant is acceptable.
+pub fn precompressed_extension(
+    accept: Option<&str>,
+    available: &[PrecompressedKind],
+) -> Option<&'static str> {
+    let codings: Vec<&str> = available.iter().map(|kind| kind.coding()).collect();
+    let (priorities, _) = priorities(accept, &codings, &EncodingOptions::default());
+
+    priorities
+        .first()
+        .map(|&(index, _)| available[index].extension())
+}
+
+/// The precompressed variants of a static file, to choose between for each request.
+///
+/// This keeps the available variants, so choosing is a single call, like the
+/// decision function of a static file server.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Precompressed {
+    available: Vec<PrecompressedKind>,
+}
+
+impl Precompressed {
+    /// Choose between these variants, the server's favourite first.
+    pub fn new(available: impl IntoIterator<Item = PrecompressedKind>) -> Self {
+        Self {
+            available: available.into_iter().collect(),
+        }
+    }
+
+    /// Get the file extension of the variant to send, as [`precompressed_extension`] does.
+    pub fn extension(&self, accept: Option<&str>) -> Option<&'static str> {
+        precompressed_extension(accept, &self.available)
+    }
+}
+
+/// Rewrite a client's Accept-Encoding header to forward to an upstream server.
+///
+/// When a proxy passes bodies through, the upstream may only use a coding
+/// both the client and the proxy support, so the header lists each of
+/// `proxy_supported` the client's header matches, at the client's quality.
+/// Excluded codings are kept with `q=0`, as is an excluded `identity`. If
+/// no coding is left acceptable, `identity` is asked for instead.
+///
+/// Otherwise the proxy decodes bodies itself, and the header lists all of
+/// `proxy_supported`.
+pub fn rewrite_accept_encoding(
+    client_header: Option<&str>,
+    proxy_supported: &[&str],
+    passthrough: bool,
+) -> String {
+    if !passthrough {
+        return match proxy_supported {
+            [] => IDENTITY.to_string(),
+            supported => supported.join(", "),
+        };
+    }
+
+    let accepts = parse_canonical(client_header);
+    let same = |a: &str, b: &str| {
+        canonical_name(a)
+            .unwrap_or(a)
+            .eq_ignore_ascii_case(canonical_name(b).unwrap_or(b))
+    };
+
+    let mut entries = Vec::new();
+    let mut acceptable = false;
+    for (index, &coding) in proxy_supported.iter().enumerate() {
+        if proxy_supported[..index]
+            .iter()
+            .any(|&other| same(other, coding))
+        {
+            continue;
+        }
+        let q = match coding_quality(&accepts, coding) {
+            Some(q) => q,
+            None => continue,
+        };
+        entries.push(if q < 1.0 {
+            format!("{};q={}", coding, q)
+        } else {
+            coding.to_string()
+        });
+        acceptable |= q > 0.0;
+    }
+
+    let identity_excluded = coding_quality(&accepts, IDENTITY) == Some(0.0);
+    if identity_excluded && !proxy_supported.iter().any(|&coding| same(coding, IDENTITY)) {
+        entries.push(format!("{};q=0", IDENTITY));
+    }
+    if !acceptable && !identity_excluded {
+        entries.insert(0, IDENTITY.to_string());
+    }
+    entries.join(", ")
+}
@@ -0,0 +1,89 @@
+//! Errors from strict negotiation.
+
+use std::error::Error;
+use std::fmt;
+
+/// A header which strict negotiation refused, because it exceeded a [`Limits`](crate::Limits)
+/// or was malformed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NegotiationError {
+    /// The header is longer than `max_header_len`.
+    HeaderTooLong,
+    /// The header has more than `max_entries` entries.
+    TooManyEntries,
+    /// The entry at this header position has more than `max_params_per_entry` parameters.
+    TooManyParams(usize),
+    /// The entry at this header position has a token longer than `max_token_len`.
+    TokenTooLong(usize),
+    /// The header has a control character at this byte offset.
+    ControlCharacter(usize),
+    /// An entry of the header is malformed.
+    Malformed(ParseError),
+}
+
+/// A malformed header entry, found by strict negotiation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The byte offset of the entry in the header.
+    pub offset: usize,
+    /// The entry, without surrounding whitespace.
+    pub entry: String,
+    /// What is wrong with the entry.
+    pub kind: ParseErrorKind,
+}
+
+/// What is wrong with a malformed header entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The entry is empty.
+    EmptyEntry,
+    /// The entry does not start with a valid token.
+    InvalidToken,
+    /// The entry has an empty parameter, as in `a;;q=1`.
+    EmptyParameter,
+    /// A parameter has no `=value`.
+    MissingValue,
+    /// The `q` parameter is not a number.
+    InvalidQuality,
+    /// The entry has more than one `q` parameter.
+    DuplicateQuality,
+}
+
+impl fmt::Display for NegotiationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NegotiationError::HeaderTooLong => write!(f, "header is too long"),
+            NegotiationError::TooManyEntries => write!(f, "header has too many entries"),
+            NegotiationError::TooManyParams(i) => {
+                write!(f, "header entry {} has too many parameters", i)
+            }
+            NegotiationError::TokenTooLong(i) => write!(f, "header entry {} is too long", i),
+            NegotiationError::ControlCharacter(at) => {
+                write!(f, "header has a control character at byte {}", at)
+            }
+            NegotiationError::Malformed(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for NegotiationError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            ParseErrorKind::EmptyEntry => "is empty",
+            ParseErrorKind::InvalidToken => "has no valid token",
+            ParseErrorKind::EmptyParameter => "has an empty parameter",
+            ParseErrorKind::MissingValue => "has a parameter without a value",
+            ParseErrorKind::InvalidQuality => "has an invalid q-value",
+            ParseErrorKind::DuplicateQuality => "has more than one q-value",
+        };
+        write!(
+            f,
+            "header entry {:?} at byte {} {}",
+            self.entry, self.offset, reason
+        )
+    }
+}
+
+impl Error for ParseError {}
@@ -0,0 +1,56 @@
+//! Adapters for reading negotiation headers out of any header map.
+
+/// A collection of request headers which negotiation headers can be read from.
+///
+/// Implementations must look names up case-insensitively and return every
+/// value of a repeated header, in order.
+pub trait HeaderSource {
+    /// Get all values of the named header.
+    fn get_all(&self, name: &str) -> Vec<&str>;
+}
+
+impl<'a> HeaderSource for [(&'a str, &'a str)] {
+    fn get_all(&self, name: &str) -> Vec<&str> {
+        self.iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+            .collect()
+    }
+}
+
+#[cfg(feature = "http")]
+impl HeaderSource for http::HeaderMap {
+    fn get_all(&self, name: &str) -> Vec<&str> {
+        http::HeaderMap::get_all(self, name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect()
+    }
+}
+
+#[cfg(feature = "http-types")]
+impl HeaderSource for http_types::Headers {
+    fn get_all(&self, name: &str) -> Vec<&str> {
+        let name: http_types::headers::HeaderName = match name.parse() {
+            Ok(name) => name,
+            Err(_) => return Vec::new(),
+        };
+
+        match self.get(name) {
+            Some(values) => values.iter().map(|value| value.as_str()).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Get a header as a single value, joining repeated headers as a list.
+///
+/// Returns `None` if the header is absent.
+pub fn combined_header(headers: &(impl HeaderSource + ?Sized), name: &str) -> Option<String> {
+    let values = headers.get_all(name);
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.join(", "))
+    }
+}
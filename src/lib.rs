@@ -1,19 +1,279 @@
 //! An HTTP content negotiator for http-rs.
+//!
+//! Each negotiation axis has its own module, such as [`charset`](mod@charset). The
+//! functions at the top level of the crate are shorthands for them.
 
-mod charsets;
-mod encodings;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod charset;
+pub mod encoding;
+mod error;
+mod header_source;
 mod languages;
 mod media_types;
+mod options;
+mod stats;
+mod tokens;
+pub mod transfer_encoding;
+#[cfg(feature = "headers")]
+mod typed_headers;
+
+pub use error::{NegotiationError, ParseError, ParseErrorKind};
+pub use header_source::{combined_header, HeaderSource};
+pub use options::{HeaderKind, Limits, NegotiationOptions, UnmatchedCallback};
+pub use stats::{header_stats, HeaderStats};
+pub use tokens::{
+    acceptable_tokens, is_token_acceptable, parse_tokens, preferred_token_batch,
+    preferred_token_indices, preferred_token_where, preferred_tokens, preferred_tokens_weighted,
+    preferred_tokens_with_options, preferred_tokens_with_quality, rejected_tokens, token_priority,
+    token_specificity, try_preferred_tokens_with_options, AcceptToken, BatchSummary, Specificity,
+    TokenOptions,
+};
 
 pub fn charset(accept_header: Option<&str>, available: &[&str]) -> Option<String> {
-    let set = charsets(accept_header, available);
-    if set.len() > 0 {
-        Some(set[0].to_owned())
-    } else {
-        None
-    }
-}
-  
+    negotiate_charset(accept_header, available).into_matched()
+}
+
+pub fn negotiate_charset(
+    accept_header: Option<&str>,
+    available: &[&str],
+) -> charset::CharsetOutcome {
+    charset::negotiate(accept_header, available)
+}
+
 pub fn charsets(accept_header: Option<&str>, available: &[&str]) -> Vec<String> {
-    charsets::preferred_charsets(accept_header, available)
+    charset::preferred(accept_header, available)
+}
+
+pub fn parse_accept_charset(accept_header: &str) -> Vec<AcceptToken<'_>> {
+    charset::parse(accept_header)
+}
+
+pub fn charsets_from_parsed(parsed: &[AcceptToken<'_>], available: &[&str]) -> Vec<String> {
+    charset::preferred_from_parsed(parsed, available)
+}
+
+pub fn acceptable_charsets(accept_header: Option<&str>) -> Vec<String> {
+    charset::acceptable(accept_header)
+}
+
+pub fn charset_ref<'a>(accept_header: Option<&str>, available: &[&'a str]) -> Option<&'a str> {
+    charset::most_preferred_ref(accept_header, available)
+}
+
+pub fn charsets_ref<'a>(accept_header: Option<&str>, available: &[&'a str]) -> Vec<&'a str> {
+    charset::preferred_ref(accept_header, available)
+}
+
+pub fn charset_index(accept_header: Option<&str>, available: &[&str]) -> Option<usize> {
+    charset::most_preferred_index(accept_header, available)
+}
+
+pub fn charset_indices(accept_header: Option<&str>, available: &[&str]) -> Vec<usize> {
+    charset::preferred_indices(accept_header, available)
+}
+
+pub fn charset_with_weight(
+    accept_header: Option<&str>,
+    available: &[&str],
+) -> Option<(String, f32)> {
+    charset::preferred_with_quality(accept_header, available)
+        .into_iter()
+        .next()
+}
+
+pub fn charsets_with_weights(
+    accept_header: Option<&str>,
+    available: &[&str],
+) -> Vec<(String, f32)> {
+    charset::preferred_with_quality(accept_header, available)
+}
+
+pub fn is_charset_acceptable(accept_header: Option<&str>, charset: &str) -> bool {
+    charset::is_acceptable(accept_header, charset)
+}
+
+pub fn explain_charset(
+    accept_header: Option<&str>,
+    available: &[&str],
+) -> Option<charset::CharsetExplanation> {
+    charset::explain(accept_header, available)
+}
+
+pub fn charsets_with_default(
+    accept_header: Option<&str>,
+    available: &[&str],
+    default_accept: &str,
+) -> Vec<String> {
+    charset::preferred_with_default(accept_header, available, default_accept)
+}
+
+pub fn charsets_with_min_quality(
+    accept_header: Option<&str>,
+    available: &[&str],
+    min_quality: f32,
+) -> Vec<String> {
+    charset::preferred_with_min_quality(accept_header, available, min_quality)
+}
+
+pub fn charset_server_order(accept_header: Option<&str>, available: &[&str]) -> Option<String> {
+    charset::server_preferred(accept_header, available)
+}
+
+pub fn charset_rejections(accept_header: Option<&str>, available: &[&str]) -> Vec<String> {
+    charset::rejected(accept_header, available)
+}
+
+#[cfg(feature = "encoding_rs")]
+pub fn charset_encoding(
+    accept_header: Option<&str>,
+    available: &[&str],
+) -> Option<&'static encoding_rs::Encoding> {
+    charset::encoding(accept_header, available)
+}
+
+pub fn charsets_with_aliases(accept_header: Option<&str>, available: &[&str]) -> Vec<String> {
+    charset::preferred_with_aliases(accept_header, available)
+}
+
+pub fn charsets_with_implicit_latin1(
+    accept_header: Option<&str>,
+    available: &[&str],
+) -> Vec<String> {
+    charset::preferred_with_implicit_latin1(accept_header, available)
+}
+
+pub fn charsets_weighted(accept_header: Option<&str>, available: &[(&str, f32)]) -> Vec<String> {
+    charset::preferred_weighted(accept_header, available)
+}
+
+pub fn try_charsets(
+    accept_header: Option<&str>,
+    available: &[&str],
+) -> Result<Vec<String>, NegotiationError> {
+    charset::try_preferred_with_options(accept_header, available, &NegotiationOptions::default())
+}
+
+pub fn charset_where(
+    accept_header: Option<&str>,
+    available: &[&str],
+    f: impl Fn(&str) -> bool,
+) -> Option<String> {
+    charset::preferred_where(accept_header, available, f)
+}
+
+pub fn charset_batch<'a>(
+    accept_headers: impl IntoIterator<Item = Option<&'a str>>,
+    available: &[&str],
+) -> BatchSummary {
+    charset::batch(accept_headers, available)
+}
+
+pub fn charset_from_headers(
+    headers: &(impl HeaderSource + ?Sized),
+    available: &[&str],
+) -> Option<String> {
+    charset::preferred_from_headers(headers, available)
+        .into_iter()
+        .next()
+}
+
+pub fn charsets_from_headers(
+    headers: &(impl HeaderSource + ?Sized),
+    available: &[&str],
+) -> Vec<String> {
+    charset::preferred_from_headers(headers, available)
+}
+
+pub fn encoding(accept_header: Option<&str>, available: &[&str]) -> Option<String> {
+    encoding::most_preferred(accept_header, available)
+}
+
+pub fn encodings(accept_header: Option<&str>, available: &[&str]) -> Vec<String> {
+    encoding::preferred(accept_header, available)
+}
+
+pub fn negotiate_encoding(
+    accept_header: Option<&str>,
+    available: &[&str],
+) -> Option<encoding::EncodingMatch> {
+    encoding::negotiate(accept_header, available)
+}
+
+pub fn try_encodings(
+    accept_header: Option<&str>,
+    available: &[&str],
+) -> Result<Vec<String>, NegotiationError> {
+    encoding::try_preferred_with_options(
+        accept_header,
+        available,
+        &encoding::EncodingOptions::default(),
+    )
+}
+
+pub fn encoding_with_weight(
+    accept_header: Option<&str>,
+    available: &[&str],
+) -> Option<(String, f32)> {
+    encoding::preferred_with_quality(accept_header, available)
+        .into_iter()
+        .next()
+}
+
+pub fn encodings_with_weights(
+    accept_header: Option<&str>,
+    available: &[&str],
+) -> Vec<(String, f32)> {
+    encoding::preferred_with_quality(accept_header, available)
+}
+
+pub fn is_encoding_acceptable(accept_header: Option<&str>, coding: &str) -> bool {
+    encoding::is_acceptable(accept_header, coding)
+}
+
+pub fn encodings_weighted(accept_header: Option<&str>, available: &[(&str, f32)]) -> Vec<String> {
+    encoding::preferred_weighted(accept_header, available)
+}
+
+pub fn encoding_chain<'a>(
+    accept_header: Option<&str>,
+    chains: &[&'a [&'a str]],
+) -> Option<&'a [&'a str]> {
+    encoding::preferred_chain(accept_header, chains)
+}
+
+pub fn precompressed_extension(
+    accept_encoding: Option<&str>,
+    available: &[encoding::PrecompressedKind],
+) -> Option<&'static str> {
+    encoding::precompressed_extension(accept_encoding, available)
+}
+
+pub fn rewrite_accept_encoding(
+    client_header: Option<&str>,
+    proxy_supported: &[&str],
+    passthrough: bool,
+) -> String {
+    encoding::rewrite_accept_encoding(client_header, proxy_supported, passthrough)
+}
+
+pub fn acceptable_encodings(accept_header: Option<&str>) -> Vec<String> {
+    encoding::acceptable(accept_header)
+}
+
+pub fn transfer_encoding(te_header: Option<&str>, available: &[&str]) -> Option<String> {
+    transfer_encoding::most_preferred(te_header, available)
+}
+
+pub fn te_accepts_trailers(te_header: Option<&str>) -> bool {
+    transfer_encoding::accepts_trailers(te_header)
+}
+
+pub fn preferred_tokens_from_headers(
+    headers: &(impl HeaderSource + ?Sized),
+    name: &str,
+    provided: &[&str],
+    options: &TokenOptions,
+) -> Vec<String> {
+    preferred_tokens_with_options(combined_header(headers, name).as_deref(), provided, options)
 }
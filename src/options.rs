@@ -0,0 +1,113 @@
+//! Options shared by every negotiation axis.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// The kind of negotiation header being read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HeaderKind {
+    /// An Accept-Charset header, where `*` is a wildcard.
+    AcceptCharset,
+    /// An Accept-Encoding header, where `*` is a wildcard.
+    AcceptEncoding,
+    /// A TE header, listing transfer codings without a wildcard.
+    Te,
+    /// A generic `token;q=…` list header, such as Want-Digest.
+    #[default]
+    TokenList,
+}
+
+/// A callback for header entries which matched none of the provided values.
+pub type UnmatchedCallback = Arc<dyn Fn(HeaderKind, &str) + Send + Sync>;
+
+/// Caps on how much of a header negotiation will read.
+///
+/// Negotiation ignores whatever is beyond a limit rather than failing: a
+/// header is cut back to the last whole entry within `max_header_len`,
+/// entries after the first `max_entries` are dropped before any is parsed,
+/// malformed ones included, extension parameters after the first
+/// `max_params_per_entry` of an entry are dropped, though its `q` is always
+/// read, and entries with a token
+/// longer than `max_token_len` are skipped. The `try_` functions return a
+/// [`NegotiationError`](crate::NegotiationError) instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The longest header read, in bytes.
+    pub max_header_len: usize,
+    /// The most entries read from a header.
+    pub max_entries: usize,
+    /// The most extension parameters read from an entry, besides its `q`.
+    pub max_params_per_entry: usize,
+    /// The longest token accepted in an entry, in bytes.
+    pub max_token_len: usize,
+}
+
+impl Limits {
+    pub(crate) const DEFAULT: Limits = Limits {
+        max_header_len: 8 * 1024,
+        max_entries: 64,
+        max_params_per_entry: 16,
+        max_token_len: 256,
+    };
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::DEFAULT
+    }
+}
+
+/// Options shared by every negotiation axis.
+#[derive(Clone)]
+pub struct NegotiationOptions {
+    /// Return negotiated values lowercased, for use as lookup keys.
+    ///
+    /// By default values are returned exactly as they were provided.
+    pub normalize_case: bool,
+    /// Called with every header entry which matches none of the provided values.
+    ///
+    /// Wildcards and entries excluded with `q=0` are never reported.
+    pub on_unmatched: Option<UnmatchedCallback>,
+    /// How much of a header to read.
+    pub limits: Limits,
+    /// The lowest quality, from 0.0 to 1.0, at which a match is acceptable.
+    ///
+    /// A header entry below it counts as if it had `q=0`. By default any
+    /// quality above 0.0 is acceptable.
+    pub min_quality: f32,
+}
+
+impl NegotiationOptions {
+    pub(crate) const DEFAULT: NegotiationOptions = NegotiationOptions {
+        normalize_case: false,
+        on_unmatched: None,
+        limits: Limits::DEFAULT,
+        min_quality: 0.0,
+    };
+
+    /// Prepare a negotiated value to be returned.
+    pub(crate) fn output(&self, value: &str) -> String {
+        if self.normalize_case {
+            value.to_ascii_lowercase()
+        } else {
+            value.to_owned()
+        }
+    }
+}
+
+impl Default for NegotiationOptions {
+    fn default() -> Self {
+        NegotiationOptions::DEFAULT
+    }
+}
+
+impl fmt::Debug for NegotiationOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NegotiationOptions")
+            .field("normalize_case", &self.normalize_case)
+            .field("on_unmatched", &self.on_unmatched.is_some())
+            .field("limits", &self.limits)
+            .field("min_quality", &self.min_quality)
+            .finish()
+    }
+}
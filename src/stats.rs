@@ -0,0 +1,78 @@
+//! Complexity metrics for negotiation headers.
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::options::HeaderKind;
+use crate::tokens;
+
+impl HeaderKind {
+    fn wildcard(self) -> bool {
+        match self {
+            HeaderKind::AcceptCharset | HeaderKind::AcceptEncoding => true,
+            HeaderKind::Te | HeaderKind::TokenList => false,
+        }
+    }
+}
+
+/// Complexity metrics for a negotiation header.
+///
+/// The header is read with the parser used for negotiation, so entries which
+/// are counted as malformed are those negotiation ignores. [`Limits`](crate::Limits)
+/// are not applied, so the stats show how far a header goes past them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HeaderStats {
+    /// Number of entries negotiation would use.
+    pub entries: usize,
+    /// Number of non-empty entries negotiation would ignore.
+    pub malformed: usize,
+    /// Length of the longest token, in bytes.
+    pub max_token_len: usize,
+    /// Total number of parameters across all entries, including `q`.
+    pub params: usize,
+    /// Whether any entry is a wildcard for this kind of header.
+    pub has_wildcard: bool,
+    /// Whether any entry is excluded with `q=0`.
+    pub has_exclusions: bool,
+}
+
+/// Get complexity metrics for a negotiation header.
+pub fn header_stats(header: &str, kind: HeaderKind) -> HeaderStats {
+    let mut stats = HeaderStats::default();
+
+    for (i, set) in header.split(',').enumerate() {
+        let unfolded = match tokens::unfold(set) {
+            Some(unfolded) => unfolded,
+            None => {
+                stats.malformed += 1;
+                continue;
+            }
+        };
+        let (token, params) = match tokens::split_token(&unfolded) {
+            Some(parts) => parts,
+            None => {
+                if !tokens::trim_ows(set).is_empty() {
+                    stats.malformed += 1;
+                }
+                continue;
+            }
+        };
+
+        let parsed = match tokens::parse_token(set, i) {
+            Some(parsed) => parsed,
+            None => {
+                stats.malformed += 1;
+                continue;
+            }
+        };
+
+        stats.entries += 1;
+        stats.max_token_len = stats.max_token_len.max(token.len());
+        stats.params += tokens::params(params).count();
+        stats.has_wildcard |= kind.wildcard() && token == "*";
+        stats.has_exclusions |= parsed.quality() == 0.0;
+    }
+
+    stats
+}
@@ -0,0 +1,1335 @@
+//
+// negotiator
+// Copyright(c) 2012 Isaac Z. Schlueter
+// Copyright(c) 2014 Federico Romero
+// Copyright(c) 2014-2015 Douglas Christopher Wilson
+// Copyright(c) 2020 Jeremiah Senkpiel
+// MIT Licensed
+//
+
+//! Negotiation over headers of the form `token;q=…, token;q=…`.
+//!
+//! Accept-Charset negotiation is a thin wrapper over this, with `*` treated
+//! as a wildcard and a missing header meaning `*`. Other headers sharing the
+//! grammar, such as Want-Digest, can be negotiated with it directly.
+//!
+//! Tokens are matched ASCII case-insensitively, which is what the HTTP token
+//! grammar calls for. Non-ASCII letters must match exactly.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::error::{NegotiationError, ParseError, ParseErrorKind};
+use crate::options::{HeaderKind, Limits, NegotiationOptions};
+
+/// Compiled on first use, then shared by every negotiation.
+static SIMPLE_TOKEN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[ \t]*([^\s;]+)[ \t]*(?:;(.*))?$").unwrap());
+
+/// Qualities are stored in thousandths, the precision of an HTTP qvalue.
+const MAX_QUALITY: isize = 1000;
+
+// Most headers and provided lists are short, so with the `smallvec` feature
+// the collections built during negotiation stay on the stack.
+#[cfg(feature = "smallvec")]
+type Entries<'a> = smallvec::SmallVec<[AcceptToken<'a>; 8]>;
+#[cfg(not(feature = "smallvec"))]
+type Entries<'a> = Vec<AcceptToken<'a>>;
+
+#[cfg(feature = "smallvec")]
+type Priorities = smallvec::SmallVec<[Specificity; 8]>;
+#[cfg(not(feature = "smallvec"))]
+type Priorities = Vec<Specificity>;
+
+#[cfg(feature = "smallvec")]
+type Indices = smallvec::SmallVec<[usize; 8]>;
+#[cfg(not(feature = "smallvec"))]
+type Indices = Vec<usize>;
+
+/// Options for token list negotiation.
+#[derive(Clone, Debug, Default)]
+pub struct TokenOptions {
+    /// Whether a `*` entry in the header matches any provided token.
+    pub wildcard: bool,
+    /// The kind of header being negotiated, as reported to callbacks.
+    pub kind: HeaderKind,
+    /// Options shared with the other negotiation axes.
+    pub negotiation: NegotiationOptions,
+}
+
+/// An entry parsed from a token list header.
+///
+/// Entries borrow their token from the header they were parsed from; use
+/// [`AcceptToken::into_owned`] to keep one around longer.
+#[derive(Clone, Debug)]
+pub struct AcceptToken<'a> {
+    token: Cow<'a, str>,
+    params: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+    q: isize,
+    i: usize,
+    /// Added by [`imply_token`] rather than parsed from the header.
+    implied: bool,
+    /// The token as written in the header, if [`canonicalize_tokens`] replaced it.
+    alias: Option<Cow<'a, str>>,
+}
+
+impl AcceptToken<'_> {
+    /// Copy the token so the entry no longer borrows from the header.
+    pub fn into_owned(self) -> AcceptToken<'static> {
+        AcceptToken {
+            token: Cow::Owned(self.token.into_owned()),
+            params: self
+                .params
+                .into_iter()
+                .map(|(key, value)| (Cow::Owned(key.into_owned()), Cow::Owned(value.into_owned())))
+                .collect(),
+            q: self.q,
+            i: self.i,
+            implied: self.implied,
+            alias: self.alias.map(|alias| Cow::Owned(alias.into_owned())),
+        }
+    }
+
+    /// The token, as written in the header.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// The parameters of the entry other than `q`, as written in the header.
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params
+            .iter()
+            .map(|(key, value)| (key.as_ref(), value.as_ref()))
+    }
+
+    /// The quality of the entry, from 0.0 to 1.0.
+    pub fn quality(&self) -> f32 {
+        to_weight(self.q)
+    }
+
+    /// The position of the entry in the header.
+    pub fn index(&self) -> usize {
+        self.i
+    }
+}
+
+impl fmt::Display for AcceptToken<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.token)?;
+        for (key, value) in self.params() {
+            write!(f, ";{}={}", key, value)?;
+        }
+        if self.q < MAX_QUALITY {
+            write!(f, ";q={}", to_weight(self.q))?;
+        }
+        Ok(())
+    }
+}
+
+/// How well a header entry matches a candidate token.
+///
+/// Compare specificities with [`Specificity::compare`] to rank candidates the
+/// same way negotiation does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Specificity {
+    i: Option<usize>,
+    o: isize,
+    q: isize,
+    s: isize,
+}
+
+impl Specificity {
+    /// The quality of the matching header entry, from 0.0 to 1.0.
+    ///
+    /// A candidate with a quality of 0.0 is not acceptable.
+    pub fn quality(&self) -> f32 {
+        to_weight(self.q)
+    }
+
+    /// The position of the matching entry among the header's list elements.
+    ///
+    /// Empty elements are counted, so this is the element's position when the
+    /// header is split on commas.
+    pub fn entry_index(&self) -> Option<usize> {
+        if self.o < 0 {
+            None
+        } else {
+            Some(self.o as usize)
+        }
+    }
+
+    /// Whether the matching entry names the candidate, rather than being a wildcard.
+    pub fn is_exact(&self) -> bool {
+        self.s & 1 != 0
+    }
+
+    /// Compare two specificities, ordering the more preferred one first.
+    ///
+    /// Higher quality wins, then an exact match over a wildcard, then the
+    /// entry appearing earlier in the header. Candidates that are still equal
+    /// should keep their own order, e.g. by using a stable sort.
+    pub fn compare(&self, other: &Self) -> Ordering {
+        compare_priority(self, other)
+    }
+}
+
+impl Default for Specificity {
+    fn default() -> Self {
+        Self {
+            i: None,
+            o: -1,
+            q: 0,
+            s: 0,
+        }
+    }
+}
+
+/// Parse a token list header.
+///
+/// Entries which cannot be parsed are skipped, and the default [`Limits`]
+/// apply.
+pub fn parse_tokens(accept: &str) -> Vec<AcceptToken<'_>> {
+    parse_tokens_within(accept, &Limits::default())
+}
+
+/// Parse a token list header, ignoring whatever is beyond the limits.
+pub(crate) fn parse_tokens_within<'a>(accept: &'a str, limits: &Limits) -> Vec<AcceptToken<'a>> {
+    parse_lenient(accept, limits).into_iter().collect()
+}
+
+/// Parse a header of bare tokens separated by `", "`, with no parameters.
+///
+/// This is what most clients send, and splitting it needs no regex. Returns
+/// `None` for any other header, or one beyond the limits, for
+/// [`parse_tokens_within`] to parse instead.
+pub(crate) fn parse_bare_tokens<'a>(
+    accept: &'a str,
+    limits: &Limits,
+) -> Option<Vec<AcceptToken<'a>>> {
+    if accept.len() > limits.max_header_len {
+        return None;
+    }
+
+    let mut parsed = Vec::with_capacity(accept.matches(',').count() + 1);
+    for (i, token) in accept.split(", ").enumerate() {
+        if token.is_empty()
+            || token.len() > limits.max_token_len
+            || i == limits.max_entries
+            || !token.bytes().all(is_tchar)
+        {
+            return None;
+        }
+        parsed.push(AcceptToken {
+            token: Cow::Borrowed(token),
+            params: Vec::new(),
+            q: MAX_QUALITY,
+            i,
+            implied: false,
+            alias: None,
+        });
+    }
+    Some(parsed)
+}
+
+/// Check if a byte may appear in a token, as RFC 9110 section 5.6.2 defines it.
+fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+/// Parse a token list header, failing on malformed entries or ones beyond the limits.
+pub(crate) fn try_parse_tokens_within<'a>(
+    accept: &'a str,
+    limits: &Limits,
+) -> Result<Vec<AcceptToken<'a>>, NegotiationError> {
+    Ok(parse_strict(accept, limits)?.into_iter().collect())
+}
+
+/// Add an entry for a token after the others, unless one of them matches it.
+///
+/// The entry has the lowest quality of the others, counting an excluded one
+/// as 1.0, and matches no more specifically than a wildcard, so the tokens
+/// the header names are preferred to it. Returns whether it was added.
+pub(crate) fn imply_token<'a>(
+    accepts: &mut Vec<AcceptToken<'a>>,
+    token: &'a str,
+    wildcard: bool,
+) -> bool {
+    let q = accepts
+        .iter()
+        .map(|accept| if accept.q > 0 { accept.q } else { MAX_QUALITY })
+        .min()
+        .unwrap_or(MAX_QUALITY);
+    imply_token_with_quality(accepts, token, wildcard, to_weight(q))
+}
+
+/// Add an entry for a token after the others at a quality, unless one of them matches it.
+///
+/// Like [`imply_token`], the entry matches no more specifically than a wildcard.
+pub(crate) fn imply_token_with_quality<'a>(
+    accepts: &mut Vec<AcceptToken<'a>>,
+    token: &'a str,
+    wildcard: bool,
+    quality: f32,
+) -> bool {
+    if accepts
+        .iter()
+        .any(|accept| specify(token, accept, 0, wildcard).is_some())
+    {
+        return false;
+    }
+
+    let q = server_quality(quality);
+    let i = accepts.last().map_or(0, |accept| accept.i + 1);
+    accepts.push(AcceptToken {
+        token: Cow::Borrowed(token),
+        params: Vec::new(),
+        q,
+        i,
+        implied: true,
+        alias: None,
+    });
+    true
+}
+
+/// Parse a token list header, ignoring whatever is beyond the limits.
+fn parse_lenient<'a>(accept: &'a str, limits: &Limits) -> Entries<'a> {
+    let capacity = accept.matches(',').count() + 1;
+    let mut parsed = Entries::with_capacity(capacity.min(limits.max_entries));
+    parsed.extend(lenient_entries(accept, limits));
+    parsed
+}
+
+/// Iterate over the entries of a token list header, ignoring whatever is beyond the limits.
+fn lenient_entries<'a>(accept: &'a str, limits: &Limits) -> impl Iterator<Item = AcceptToken<'a>> {
+    let limits = *limits;
+    let accept = if accept.len() > limits.max_header_len {
+        truncate_entries(accept, limits.max_header_len)
+    } else {
+        accept
+    };
+
+    accept
+        .split(',')
+        .enumerate()
+        // Cap the entries before parsing any, so a huge header costs no more than a full one.
+        .filter(|(_, set)| !trim_ows(set).is_empty())
+        .take(limits.max_entries)
+        // Only strict parsing fails.
+        .filter_map(move |(i, set)| parse_limited_token(set, i, &limits, false).unwrap_or(None))
+}
+
+/// Parse a token list header, failing if it goes beyond the limits.
+fn parse_strict<'a>(accept: &'a str, limits: &Limits) -> Result<Entries<'a>, NegotiationError> {
+    if accept.len() > limits.max_header_len {
+        return Err(NegotiationError::HeaderTooLong);
+    }
+
+    let capacity = accept.matches(',').count() + 1;
+    let mut parsed = Entries::with_capacity(capacity.min(limits.max_entries));
+
+    // An empty header has no entries, rather than one empty entry.
+    if trim_ows(accept).is_empty() {
+        return Ok(Entries::new());
+    }
+
+    let mut start = 0;
+    for (i, set) in accept.split(',').enumerate() {
+        if let Some(at) = find_control(set) {
+            return Err(NegotiationError::ControlCharacter(start + at));
+        }
+        if let Err(kind) = check_entry(&unfold(set).unwrap_or_default()) {
+            return Err(NegotiationError::Malformed(ParseError {
+                offset: start,
+                entry: trim_ows(set).to_string(),
+                kind,
+            }));
+        }
+        start += set.len() + 1;
+
+        if let Some(token) = parse_limited_token(set, i, limits, true)? {
+            if parsed.len() == limits.max_entries {
+                return Err(NegotiationError::TooManyEntries);
+            }
+            parsed.push(token);
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Check that an entry follows the grammar, for strict parsing.
+fn check_entry(set: &str) -> Result<(), ParseErrorKind> {
+    if trim_ows(set).is_empty() {
+        return Err(ParseErrorKind::EmptyEntry);
+    }
+    let (token, raw) = split_token(set).ok_or(ParseErrorKind::InvalidToken)?;
+    if !token.bytes().all(is_tchar) {
+        return Err(ParseErrorKind::InvalidToken);
+    }
+
+    let mut has_quality = false;
+    for param in raw.into_iter().flat_map(|raw| raw.split(';')) {
+        let (key, value) = match trim_ows(param).split_once('=') {
+            Some(parts) => parts,
+            None if trim_ows(param).is_empty() => return Err(ParseErrorKind::EmptyParameter),
+            None => return Err(ParseErrorKind::MissingValue),
+        };
+        if key == "q" {
+            if !is_qvalue(value) {
+                return Err(ParseErrorKind::InvalidQuality);
+            }
+            if has_quality {
+                return Err(ParseErrorKind::DuplicateQuality);
+            }
+            has_quality = true;
+        }
+    }
+
+    Ok(())
+}
+
+/// Cut a header back to the whole entries within its first `max` bytes.
+fn truncate_entries(accept: &str, max: usize) -> &str {
+    let end = accept.as_bytes()[..=max]
+        .iter()
+        .rposition(|&b| b == b',')
+        .unwrap_or(0);
+    &accept[..end]
+}
+
+/// Parse a token from a token list header, within limits.
+///
+/// Entries with control characters are skipped, after obs-folds are unfolded.
+fn parse_limited_token<'a>(
+    set: &'a str,
+    i: usize,
+    limits: &Limits,
+    strict: bool,
+) -> Result<Option<AcceptToken<'a>>, NegotiationError> {
+    match unfold(set) {
+        Some(Cow::Borrowed(set)) => parse_unfolded_token(set, i, limits, strict),
+        Some(Cow::Owned(set)) => {
+            Ok(parse_unfolded_token(&set, i, limits, strict)?.map(AcceptToken::into_owned))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Parse a token from an entry without control characters, within limits.
+fn parse_unfolded_token<'a>(
+    set: &'a str,
+    i: usize,
+    limits: &Limits,
+    strict: bool,
+) -> Result<Option<AcceptToken<'a>>, NegotiationError> {
+    let (token, raw) = match split_token(set) {
+        Some(parts) => parts,
+        None => return Ok(None),
+    };
+
+    if token.len() > limits.max_token_len {
+        if strict {
+            return Err(NegotiationError::TokenTooLong(i));
+        }
+        return Ok(None);
+    }
+    // As when reading leniently, the q doesn't count toward the cap.
+    let mut extensions = params(raw).filter(|param| !matches!(param, ("q", Some(_))));
+    if strict && extensions.nth(limits.max_params_per_entry).is_some() {
+        return Err(NegotiationError::TooManyParams(i));
+    }
+
+    Ok(token_from_parts(token, raw, i, limits.max_params_per_entry))
+}
+
+/// Parse a token from a token list header, without limits.
+pub(crate) fn parse_token(set: &str, i: usize) -> Option<AcceptToken<'_>> {
+    match unfold(set)? {
+        Cow::Borrowed(set) => {
+            let (token, raw) = split_token(set)?;
+            token_from_parts(token, raw, i, usize::MAX)
+        }
+        Cow::Owned(set) => {
+            let (token, raw) = split_token(&set)?;
+            token_from_parts(token, raw, i, usize::MAX).map(AcceptToken::into_owned)
+        }
+    }
+}
+
+/// Strip the optional whitespace, spaces and tabs, around a list element or parameter.
+pub(crate) fn trim_ows(value: &str) -> &str {
+    value.trim_matches([' ', '\t'])
+}
+
+/// Replace each obs-fold in an entry with a space.
+///
+/// Returns `None` if the entry has any other control character, besides tab.
+pub(crate) fn unfold(set: &str) -> Option<Cow<'_, str>> {
+    if find_control(set).is_some() {
+        return None;
+    }
+    if !set.contains('\r') {
+        return Some(Cow::Borrowed(set));
+    }
+
+    let mut unfolded = String::with_capacity(set.len());
+    let mut rest = set;
+    while let Some(at) = rest.find("\r\n") {
+        unfolded.push_str(&rest[..at]);
+        unfolded.push(' ');
+        rest = rest[at + 2..].trim_start_matches([' ', '\t']);
+    }
+    unfolded.push_str(rest);
+
+    Some(Cow::Owned(unfolded))
+}
+
+/// Find the first control character in an entry which is neither a tab nor part of an obs-fold.
+fn find_control(set: &str) -> Option<usize> {
+    let bytes = set.as_bytes();
+    let mut at = 0;
+    while at < bytes.len() {
+        match &bytes[at..] {
+            // obs-fold = CRLF 1*( SP / HTAB )
+            [b'\r', b'\n', b' ', ..] | [b'\r', b'\n', b'\t', ..] => at += 3,
+            [b'\t', ..] => at += 1,
+            [b, ..] if b.is_ascii_control() => return Some(at),
+            _ => at += 1,
+        }
+    }
+    None
+}
+
+/// Build a token from its parts, reading at most `max_params` parameters.
+///
+/// Parameters other than `q` are kept, unless they have no value. Returns
+/// `None` if a `q` can't be parsed, so the entry is skipped rather than
+/// taking the default quality.
+fn token_from_parts<'a>(
+    token: &'a str,
+    raw: Option<&'a str>,
+    i: usize,
+    max_params: usize,
+) -> Option<AcceptToken<'a>> {
+    let mut q = MAX_QUALITY;
+    let mut extensions = Vec::new();
+    for (key, value) in params(raw) {
+        match (key, value) {
+            ("q", Some(value)) => q = parse_quality(value)?.clamp(0, MAX_QUALITY),
+            // Only extensions are capped, so a q after them still excludes the entry.
+            (key, Some(value)) if extensions.len() < max_params => {
+                extensions.push((Cow::Borrowed(key), Cow::Borrowed(value)))
+            }
+            _ => {}
+        }
+    }
+
+    Some(AcceptToken {
+        token: Cow::Borrowed(token),
+        params: extensions,
+        q,
+        i,
+        implied: false,
+        alias: None,
+    })
+}
+
+/// Split a token list entry into its token and its `;` separated parameters.
+pub(crate) fn split_token(set: &str) -> Option<(&str, Option<&str>)> {
+    let captures = SIMPLE_TOKEN.captures(set)?;
+
+    let token = captures.get(1)?.as_str();
+    let params = captures.get(2).map(|params| params.as_str());
+
+    Some((token, params))
+}
+
+/// Iterate over the `;` separated parameters of an entry as keys and values.
+///
+/// Empty parameters are skipped and a parameter without `=` has no value.
+pub(crate) fn params(raw: Option<&str>) -> impl Iterator<Item = (&str, Option<&str>)> {
+    raw.into_iter()
+        .flat_map(|raw| raw.split(';'))
+        .map(trim_ows)
+        .filter(|param| !param.is_empty())
+        .map(|param| match param.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None => (param, None),
+        })
+}
+
+/// Parse a qvalue into thousandths.
+///
+/// Decimals past the third are truncated. Outside the grammar only a leading
+/// `-` or a missing integer part is understood, as in `-1` or `.5`.
+fn parse_quality(value: &str) -> Option<isize> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let (int, frac) = value.split_once('.').unwrap_or((value, ""));
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if (int.is_empty() && frac.is_empty()) || !is_digits(int) || !is_digits(frac) {
+        return None;
+    }
+
+    let int: isize = if int.is_empty() {
+        0
+    } else {
+        int.parse().unwrap_or(isize::MAX)
+    };
+    let frac = frac
+        .bytes()
+        .chain(iter::repeat(b'0'))
+        .take(3)
+        .fold(0, |q, b| q * 10 + isize::from(b - b'0'));
+    let q = int.saturating_mul(MAX_QUALITY).saturating_add(frac);
+    Some(if negative { -q } else { q })
+}
+
+/// Check that a qvalue follows the grammar, `0` to `1` with at most three decimals.
+fn is_qvalue(value: &str) -> bool {
+    let (int, frac) = value.split_once('.').unwrap_or((value, ""));
+    let decimals = frac.len() <= 3 && frac.bytes().all(|b| b.is_ascii_digit());
+    match int {
+        "0" => decimals,
+        "1" => decimals && frac.bytes().all(|b| b == b'0'),
+        _ => false,
+    }
+}
+
+/// Get the priority of a token.
+fn get_token_priority(
+    token: &str,
+    accepted: &[AcceptToken],
+    index: usize,
+    wildcard: bool,
+) -> Specificity {
+    let mut priority = Specificity::default();
+
+    for accept in accepted {
+        update_priority(&mut priority, token, accept, index, wildcard);
+    }
+
+    priority
+}
+
+/// Replace the priority of a token if a header entry is a better match for it.
+fn update_priority(
+    priority: &mut Specificity,
+    token: &str,
+    accept: &AcceptToken,
+    index: usize,
+    wildcard: bool,
+) {
+    if let Some(spec) = specify(token, accept, index, wildcard) {
+        // The most specific entry wins, then the highest quality, then the first.
+        if priority.i.is_none() || (priority.s, priority.q) < (spec.s, spec.q) {
+            *priority = spec
+        }
+    }
+}
+
+/// Get the specificity of the token.
+fn specify(token: &str, spec: &AcceptToken, index: usize, wildcard: bool) -> Option<Specificity> {
+    let mut s = 0;
+    if spec.token.eq_ignore_ascii_case(token) {
+        if !spec.implied {
+            s |= 1;
+        }
+    } else if !wildcard || spec.token != "*" {
+        return None;
+    }
+
+    Some(Specificity {
+        i: Some(index),
+        o: spec.i as isize,
+        q: spec.q,
+        s,
+    })
+}
+
+/// Get the specificity of a header entry for a candidate token.
+///
+/// Returns `None` if the entry does not match the candidate.
+pub fn token_specificity(
+    range: &AcceptToken,
+    candidate: &str,
+    options: &TokenOptions,
+) -> Option<Specificity> {
+    let mut spec = specify(candidate, range, 0, options.wildcard)?;
+    spec.i = None;
+    Some(spec)
+}
+
+/// Get the specificity of the header entry which best matches a candidate token.
+///
+/// Returns `None` if no entry matches the candidate.
+pub fn token_priority(
+    ranges: &[AcceptToken],
+    candidate: &str,
+    options: &TokenOptions,
+) -> Option<Specificity> {
+    let mut priority = get_token_priority(candidate, ranges, 0, options.wildcard);
+    priority.i?;
+    priority.i = None;
+    Some(priority)
+}
+
+/// Get the preferred tokens from a token list header.
+///
+/// A missing header means any token is acceptable, and an empty provided
+/// list has no preferred tokens; see [`acceptable_tokens`] to list the header.
+pub fn preferred_tokens(header: Option<&str>, provided: &[&str]) -> Vec<String> {
+    preferred_tokens_with_options(header, provided, &TokenOptions::default())
+}
+
+/// Get the preferred tokens from a token list header, with options.
+pub fn preferred_tokens_with_options(
+    header: Option<&str>,
+    provided: &[&str],
+    options: &TokenOptions,
+) -> Vec<String> {
+    preferred_tokens_with_quality(header, provided, options)
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect()
+}
+
+/// Get the preferred tokens from a token list header along with their quality.
+pub fn preferred_tokens_with_quality(
+    header: Option<&str>,
+    provided: &[&str],
+    options: &TokenOptions,
+) -> Vec<(String, f32)> {
+    // Lenient negotiation never fails.
+    negotiate_with_quality(header, provided, options, false).unwrap_or_default()
+}
+
+/// Get the preferred tokens from a token list header, failing if it exceeds the limits.
+pub fn try_preferred_tokens_with_options(
+    header: Option<&str>,
+    provided: &[&str],
+    options: &TokenOptions,
+) -> Result<Vec<String>, NegotiationError> {
+    let preferred = negotiate_with_quality(header, provided, options, true)?;
+
+    Ok(preferred.into_iter().map(|(token, _)| token).collect())
+}
+
+/// Negotiate a token list header, failing on headers beyond the limits when strict.
+fn negotiate_with_quality(
+    header: Option<&str>,
+    provided: &[&str],
+    options: &TokenOptions,
+    strict: bool,
+) -> Result<Vec<(String, f32)>, NegotiationError> {
+    let (accept, wildcard) = header_or_any(header, options);
+    if !provided.is_empty() && accepts_any(accept, wildcard, provided) {
+        return Ok(unique_indices(provided, &options.negotiation)
+            .iter()
+            .map(|&index| (options.negotiation.output(provided[index]), 1.0))
+            .collect());
+    }
+    let limits = &options.negotiation.limits;
+    let accepts = if strict {
+        parse_strict(accept, limits)?
+    } else {
+        parse_lenient(accept, limits)
+    };
+
+    Ok(preferred_parsed_with_quality(
+        &accepts, provided, wildcard, options,
+    ))
+}
+
+/// Get the preferred tokens from an already parsed header along with their quality.
+pub(crate) fn preferred_parsed_with_quality(
+    accepts: &[AcceptToken],
+    provided: &[&str],
+    wildcard: bool,
+    options: &TokenOptions,
+) -> Vec<(String, f32)> {
+    let negotiation = &options.negotiation;
+    report_unmatched(accepts, provided, wildcard, options);
+
+    let unique = unique_indices(provided, &options.negotiation);
+    let min = min_quality(negotiation);
+    let priorities = get_sorted_priorities(accepts, provided, &unique, wildcard, min);
+
+    // sorted list of accepted tokens
+    priorities
+        .iter()
+        .filter_map(|priority| Some((priority.i?, priority.q)))
+        .map(|(index, q)| (negotiation.output(provided[index]), to_weight(q)))
+        .collect()
+}
+
+/// Get the positions and priorities of the acceptable provided tokens from a parsed header.
+///
+/// Equally preferred tokens are ordered by `compare`, such as
+/// [`compare_provided_order`].
+pub(crate) fn preferred_parsed_priorities(
+    accepts: &[AcceptToken],
+    provided: &[&str],
+    wildcard: bool,
+    options: &TokenOptions,
+    compare: fn(&Specificity, &Specificity) -> Ordering,
+) -> Vec<(usize, Specificity)> {
+    report_unmatched(accepts, provided, wildcard, options);
+
+    let unique = unique_indices(provided, &options.negotiation);
+    let min = min_quality(&options.negotiation);
+    let mut priorities = get_priorities(accepts, provided, &unique, wildcard, min);
+    priorities.sort_by(compare);
+    priorities
+        .iter()
+        .filter_map(|priority| Some((priority.i?, *priority)))
+        .collect()
+}
+
+/// Get the tokens a token list header accepts, most preferred first.
+///
+/// Repeated tokens are listed once and excluded ones are left out. This is
+/// the header itself rather than a negotiation, which has no results for an
+/// empty provided list.
+pub fn acceptable_tokens(header: Option<&str>, options: &TokenOptions) -> Vec<String> {
+    let (accept, _) = header_or_any(header, options);
+    let accepts = parse_lenient(accept, &options.negotiation.limits);
+
+    acceptable_parsed_with_quality(&accepts, options)
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect()
+}
+
+/// Get the tokens an already parsed header accepts along with their quality.
+pub(crate) fn acceptable_parsed_with_quality(
+    accepts: &[AcceptToken],
+    options: &TokenOptions,
+) -> Vec<(String, f32)> {
+    // sorted list of all tokens
+    let min = min_quality(&options.negotiation);
+    let mut filtered = accepts
+        .iter()
+        .filter(|spec| spec.q >= min) // Does the spec have enough quality?
+        .collect::<Vec<&AcceptToken>>();
+    filtered.sort_by(compare_tokens);
+    dedup_tokens(&mut filtered);
+    filtered
+        .iter()
+        .map(|spec| (options.negotiation.output(&spec.token), to_weight(spec.q)))
+        .collect()
+}
+
+/// Get the index of the most preferred provided token.
+///
+/// Only the best match so far for each provided token is kept while reading
+/// the header, rather than parsing it and sorting every match.
+pub(crate) fn most_preferred_token_index(
+    header: Option<&str>,
+    provided: &[&str],
+    options: &TokenOptions,
+) -> Option<usize> {
+    // Reporting unmatched entries needs every entry.
+    if options.negotiation.on_unmatched.is_some() {
+        return preferred_token_indices(header, provided, options)
+            .first()
+            .copied();
+    }
+
+    let (accept, wildcard) = header_or_any(header, options);
+    let unique = unique_indices(provided, &options.negotiation);
+    if accepts_any(accept, wildcard, provided) {
+        return unique.first().copied();
+    }
+
+    let mut best: Priorities = unique.iter().map(|_| Specificity::default()).collect();
+    for accept in lenient_entries(accept, &options.negotiation.limits) {
+        for (priority, &index) in best.iter_mut().zip(unique.iter()) {
+            update_priority(priority, provided[index], &accept, index, wildcard);
+        }
+    }
+
+    let min = min_quality(&options.negotiation);
+    best.iter()
+        .filter(|priority| priority.q >= min) // Does the spec have enough quality?
+        .min_by(|a, b| compare_priority(a, b))
+        .and_then(|priority| priority.i)
+}
+
+/// Get the position in `provided` of the most preferred token, with the header entry that matched it.
+///
+/// The entry is returned as written in the header, without surrounding whitespace.
+pub(crate) fn explain_token<'h>(
+    header: Option<&'h str>,
+    provided: &[&str],
+    options: &TokenOptions,
+) -> Option<(usize, &'h str, Specificity)> {
+    let (accept, wildcard) = header_or_any(header, options);
+    let unique = unique_indices(provided, &options.negotiation);
+    let accepts = parse_lenient(accept, &options.negotiation.limits);
+    report_unmatched(&accepts, provided, wildcard, options);
+
+    let min = min_quality(&options.negotiation);
+    let priority = *get_sorted_priorities(&accepts, provided, &unique, wildcard, min).first()?;
+    let entry = accept.split(',').nth(priority.entry_index()?)?;
+    Some((priority.i?, trim_ows(entry), priority))
+}
+
+/// Get the indices of the acceptable provided tokens, most preferred first.
+///
+/// These are the selections [`preferred_tokens_with_options`] makes, as
+/// positions in `provided`. An empty provided list has no indices.
+pub fn preferred_token_indices(
+    header: Option<&str>,
+    provided: &[&str],
+    options: &TokenOptions,
+) -> Vec<usize> {
+    let (accept, wildcard) = header_or_any(header, options);
+    let unique = unique_indices(provided, &options.negotiation);
+    if accepts_any(accept, wildcard, provided) {
+        return unique.to_vec();
+    }
+    let accepts = parse_lenient(accept, &options.negotiation.limits);
+    report_unmatched(&accepts, provided, wildcard, options);
+
+    let min = min_quality(&options.negotiation);
+    get_sorted_priorities(&accepts, provided, &unique, wildcard, min)
+        .iter()
+        .filter_map(|priority| priority.i)
+        .collect()
+}
+
+/// Get the preferred tokens from a token list header, weighted by server preference.
+///
+/// Each provided token comes with a server quality from 0.0 to 1.0, which
+/// is multiplied with the quality the header gives it before sorting. Ties
+/// are ordered as in unweighted negotiation, and a server quality of 0.0
+/// makes a token unacceptable.
+pub fn preferred_tokens_weighted(
+    header: Option<&str>,
+    provided: &[(&str, f32)],
+    options: &TokenOptions,
+) -> Vec<String> {
+    let tokens: Vec<&str> = provided.iter().map(|&(token, _)| token).collect();
+    let (accept, wildcard) = header_or_any(header, options);
+    let accepts = parse_lenient(accept, &options.negotiation.limits);
+    report_unmatched(&accepts, &tokens, wildcard, options);
+
+    let min = min_quality(&options.negotiation);
+    let mut weighted: Vec<(isize, Specificity)> = unique_indices(&tokens, &options.negotiation)
+        .iter()
+        .map(|&index| {
+            let priority = get_token_priority(tokens[index], &accepts, index, wildcard);
+            (
+                priority.q.saturating_mul(server_quality(provided[index].1)),
+                priority,
+            )
+        })
+        .filter(|&(q, priority)| q > 0 && priority.q >= min)
+        .collect();
+    weighted.sort_by(|(a_q, a), (b_q, b)| b_q.cmp(a_q).then_with(|| compare_priority(a, b)));
+
+    weighted
+        .iter()
+        .filter_map(|(_, priority)| priority.i)
+        .map(|index| options.negotiation.output(tokens[index]))
+        .collect()
+}
+
+/// Get the lowest acceptable quality in thousandths, which is never 0.
+fn min_quality(options: &NegotiationOptions) -> isize {
+    server_quality(options.min_quality).max(1)
+}
+
+/// Convert a server quality to thousandths, limited to 0.0 to 1.0.
+fn server_quality(weight: f32) -> isize {
+    if weight.is_nan() {
+        return 0;
+    }
+    (weight.clamp(0.0, 1.0) * MAX_QUALITY as f32).round() as isize
+}
+
+/// Get the indices of the acceptable provided tokens, matching tokens by their canonical names.
+///
+/// `canonical` gives the name a token is an alias of, if it is one.
+pub(crate) fn preferred_canonical_indices(
+    header: Option<&str>,
+    provided: &[&str],
+    options: &TokenOptions,
+    canonical: fn(&str) -> Option<&'static str>,
+) -> Vec<usize> {
+    let (accept, wildcard) = header_or_any(header, options);
+    let unique = unique_indices(provided, &options.negotiation);
+    if accepts_any(accept, wildcard, provided) {
+        return unique.to_vec();
+    }
+    let mut accepts = parse_lenient(accept, &options.negotiation.limits);
+    canonicalize_tokens(&mut accepts, canonical);
+    let names: Vec<&str> = provided
+        .iter()
+        .map(|&token| canonical(token).unwrap_or(token))
+        .collect();
+    report_unmatched(&accepts, &names, wildcard, options);
+
+    let min = min_quality(&options.negotiation);
+    get_sorted_priorities(&accepts, &names, &unique, wildcard, min)
+        .iter()
+        .filter_map(|priority| priority.i)
+        .collect()
+}
+
+/// Replace the token of each header entry which is an alias with its canonical name.
+pub(crate) fn canonicalize_tokens(
+    accepts: &mut [AcceptToken],
+    canonical: fn(&str) -> Option<&'static str>,
+) {
+    for accept in accepts.iter_mut() {
+        if let Some(name) = canonical(&accept.token) {
+            let alias = std::mem::replace(&mut accept.token, Cow::Borrowed(name));
+            accept.alias = Some(alias);
+        }
+    }
+}
+
+/// Check if a token would be selected were it the only one provided.
+///
+/// Reading the header stops at the first exact match with a non-zero quality.
+pub fn is_token_acceptable(header: Option<&str>, token: &str, options: &TokenOptions) -> bool {
+    let (accept, wildcard) = header_or_any(header, options);
+    is_canonical_token_acceptable(accept, wildcard, token, options, |_| None, None)
+}
+
+/// Check if a token would be selected were it the only one provided, matching canonical names.
+///
+/// `canonical` gives the name a token is an alias of, if it is one. The
+/// `implied` token is acceptable when no entry matches it, as it would be
+/// after [`imply_token`].
+pub(crate) fn is_canonical_token_acceptable(
+    accept: &str,
+    wildcard: bool,
+    token: &str,
+    options: &TokenOptions,
+    canonical: fn(&str) -> Option<&'static str>,
+    implied: Option<&str>,
+) -> bool {
+    if accepts_any(accept, wildcard, &[token]) {
+        return true;
+    }
+
+    let is_implied = matches!(implied, Some(implied) if implied.eq_ignore_ascii_case(token));
+    let token = canonical(token).unwrap_or(token);
+    let min = min_quality(&options.negotiation);
+    let mut excluded = false;
+    let mut wildcard_q = None;
+    let mut lowest_q = MAX_QUALITY;
+    for accept in lenient_entries(accept, &options.negotiation.limits) {
+        if canonical(&accept.token)
+            .unwrap_or(&accept.token)
+            .eq_ignore_ascii_case(token)
+        {
+            // A later entry for the same token could still have a higher quality.
+            if accept.q >= min {
+                return true;
+            }
+            excluded = true;
+        } else if wildcard && accept.token == "*" {
+            wildcard_q = wildcard_q.max(Some(accept.q));
+        }
+        if accept.q > 0 {
+            lowest_q = lowest_q.min(accept.q);
+        }
+    }
+
+    match wildcard_q {
+        _ if excluded => false,
+        Some(q) => q >= min,
+        None => is_implied && lowest_q >= min,
+    }
+}
+
+/// Get the provided tokens the header refuses, in provided order.
+///
+/// A token is refused when the entry deciding its priority, which is the most
+/// specific one matching it, has `q=0`. Tokens the header doesn't match at
+/// all are not refused, nor is anything when the header is missing.
+pub fn rejected_tokens(
+    header: Option<&str>,
+    provided: &[&str],
+    options: &TokenOptions,
+) -> Vec<String> {
+    let (accept, wildcard) = match header {
+        Some(header) => (header, options.wildcard),
+        None => return Vec::new(),
+    };
+    let accepts = parse_lenient(accept, &options.negotiation.limits);
+
+    unique_indices(provided, &options.negotiation)
+        .iter()
+        .map(|&index| {
+            (
+                index,
+                get_token_priority(provided[index], &accepts, index, wildcard),
+            )
+        })
+        .filter(|(_, priority)| priority.i.is_some() && priority.q == 0)
+        .map(|(index, _)| options.negotiation.output(provided[index]))
+        .collect()
+}
+
+/// Get the most preferred token which also passes the predicate.
+///
+/// The predicate is only called on acceptable tokens, in preference order,
+/// and no more tokens are checked once one passes.
+pub fn preferred_token_where(
+    header: Option<&str>,
+    provided: &[&str],
+    options: &TokenOptions,
+    f: impl Fn(&str) -> bool,
+) -> Option<String> {
+    preferred_token_indices(header, provided, options)
+        .into_iter()
+        .map(|index| options.negotiation.output(provided[index]))
+        .find(|token| f(token))
+}
+
+/// Selection counts from negotiating many headers against one provided list.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchSummary {
+    /// How many headers selected each provided token, in provided order.
+    ///
+    /// Duplicate provided tokens are only listed once.
+    pub selected: Vec<(String, usize)>,
+    /// How many headers found none of the provided tokens acceptable.
+    pub not_acceptable: usize,
+}
+
+/// Negotiate many token list headers against the same provided tokens.
+///
+/// The provided list is only prepared once, which makes this suitable for
+/// replaying logged headers. An empty provided list counts every header as
+/// not acceptable.
+pub fn preferred_token_batch<'a>(
+    headers: impl IntoIterator<Item = Option<&'a str>>,
+    provided: &[&str],
+    options: &TokenOptions,
+) -> BatchSummary {
+    let unique = unique_indices(provided, &options.negotiation);
+    let min = min_quality(&options.negotiation);
+    let mut counts = vec![0; provided.len()];
+    let mut not_acceptable = 0;
+
+    for header in headers {
+        let (accept, wildcard) = header_or_any(header, options);
+        if !unique.is_empty() && accepts_any(accept, wildcard, provided) {
+            counts[unique[0]] += 1;
+            continue;
+        }
+        let accepts = parse_lenient(accept, &options.negotiation.limits);
+        report_unmatched(&accepts, provided, wildcard, options);
+
+        let priorities = get_sorted_priorities(&accepts, provided, &unique, wildcard, min);
+        match priorities.first().and_then(|priority| priority.i) {
+            Some(index) => counts[index] += 1,
+            None => not_acceptable += 1,
+        }
+    }
+
+    BatchSummary {
+        selected: unique
+            .iter()
+            .map(|&index| (options.negotiation.output(provided[index]), counts[index]))
+            .collect(),
+        not_acceptable,
+    }
+}
+
+/// Get the header to negotiate with and whether `*` is a wildcard in it.
+///
+/// A missing header means any token is acceptable.
+fn header_or_any<'h>(header: Option<&'h str>, options: &TokenOptions) -> (&'h str, bool) {
+    match header {
+        Some(header) => (header, options.wildcard),
+        None => ("*", true),
+    }
+}
+
+/// Check if a header is a bare wildcard, which accepts every token equally.
+///
+/// A provided `*` matches the wildcard exactly, ahead of the other tokens, so
+/// it is left to the full negotiation.
+fn accepts_any(accept: &str, wildcard: bool, provided: &[&str]) -> bool {
+    wildcard && trim_ows(accept) == "*" && !provided.contains(&"*")
+}
+
+/// Call the unmatched callback for each entry no provided token matches.
+///
+/// Wildcards, excluded entries and entries the client didn't send are
+/// skipped, as is everything when no tokens are provided.
+fn report_unmatched(
+    accepts: &[AcceptToken],
+    provided: &[&str],
+    wildcard: bool,
+    options: &TokenOptions,
+) {
+    let on_unmatched = match &options.negotiation.on_unmatched {
+        Some(on_unmatched) => on_unmatched,
+        None => return,
+    };
+    if provided.is_empty() {
+        return;
+    }
+
+    for accept in accepts {
+        if accept.q == 0 || accept.implied || (wildcard && accept.token == "*") {
+            continue;
+        }
+        if !provided
+            .iter()
+            .any(|token| specify(token, accept, 0, false).is_some())
+        {
+            // Report what the client sent, rather than the canonical name.
+            on_unmatched(
+                options.kind,
+                accept.alias.as_deref().unwrap_or(&accept.token),
+            );
+        }
+    }
+}
+
+/// Get the indices of the provided tokens which are not duplicates.
+///
+/// Provided tokens identical to an earlier one are skipped so that each
+/// token appears at most once. Tokens differing only in case are kept,
+/// unless the output is normalized to lowercase.
+fn unique_indices(provided: &[&str], options: &NegotiationOptions) -> Indices {
+    (0..provided.len())
+        .filter(|&index| !is_duplicate(provided[index], &provided[..index], options.normalize_case))
+        .collect()
+}
+
+/// Get the priorities of the acceptable provided tokens, most preferred first.
+///
+/// Tokens with a quality below `min` thousandths are not acceptable.
+fn get_sorted_priorities(
+    accepts: &[AcceptToken],
+    provided: &[&str],
+    unique: &[usize],
+    wildcard: bool,
+    min: isize,
+) -> Priorities {
+    let mut priorities = get_priorities(accepts, provided, unique, wildcard, min);
+    priorities.sort_by(compare_priority);
+    priorities
+}
+
+/// Get the priorities of the acceptable provided tokens, in provided order.
+fn get_priorities(
+    accepts: &[AcceptToken],
+    provided: &[&str],
+    unique: &[usize],
+    wildcard: bool,
+    min: isize,
+) -> Priorities {
+    unique
+        .iter()
+        .map(|&index| get_token_priority(provided[index], accepts, index, wildcard))
+        .filter(|spec| spec.q >= min) // Does the spec have enough quality?
+        .collect()
+}
+
+/// Remove repeated header tokens from a sorted list, keeping the first (most preferred).
+fn dedup_tokens(sorted: &mut Vec<&AcceptToken>) {
+    let mut kept = 0;
+    for index in 0..sorted.len() {
+        let token = &sorted[index].token;
+        if !sorted[..kept]
+            .iter()
+            .any(|prev| prev.token.eq_ignore_ascii_case(token))
+        {
+            sorted.swap(kept, index);
+            kept += 1;
+        }
+    }
+    sorted.truncate(kept);
+}
+
+/// Check if a token was already provided, optionally ignoring case.
+fn is_duplicate(token: &str, previous: &[&str], ignore_case: bool) -> bool {
+    previous.iter().any(|&prev| {
+        if ignore_case {
+            prev.eq_ignore_ascii_case(token)
+        } else {
+            prev == token
+        }
+    })
+}
+
+/// Convert a quality in thousandths to a weight.
+fn to_weight(q: isize) -> f32 {
+    q as f32 / MAX_QUALITY as f32
+}
+
+/// Compare two Tokens.
+fn compare_tokens(a: &&AcceptToken, b: &&AcceptToken) -> Ordering {
+    // (b.q - a.q) || (b.s - a.s) || (a.o - b.o) || (a.i - b.i) || 0;
+
+    b.q.cmp(&a.q).then_with(|| a.i.cmp(&b.i))
+}
+
+/// Compare two Specificities.
+///
+/// Each step compares integer keys, so this is a total order which sorting
+/// can rely on.
+fn compare_priority(a: &Specificity, b: &Specificity) -> Ordering {
+    // (b.q - a.q) || (b.s - a.s) || (a.o - b.o) || (a.i - b.i) || 0;
+
+    b.q.cmp(&a.q)
+        .then_with(|| b.s.cmp(&a.s))
+        .then_with(|| a.o.cmp(&b.o))
+        .then_with(|| a.i.unwrap_or(0).cmp(&b.i.unwrap_or(0)))
+}
+
+/// Compare two Specificities, breaking ties by the order of the provided tokens.
+///
+/// Unlike [`compare_priority`], where the matching entry is in the header
+/// doesn't matter, so the provided order decides between equally good matches.
+pub(crate) fn compare_provided_order(a: &Specificity, b: &Specificity) -> Ordering {
+    b.q.cmp(&a.q)
+        .then_with(|| b.s.cmp(&a.s))
+        .then_with(|| a.i.unwrap_or(0).cmp(&b.i.unwrap_or(0)))
+}
+
+/// Weigh priorities by server preference, as [`preferred_tokens_weighted`] does.
+///
+/// `weight` gives the server quality of a provided index. Priorities whose
+/// weighted quality is 0 are dropped, and ties are broken as in
+/// [`compare_provided_order`], without regard to the header's quality.
+pub(crate) fn weigh_priorities(
+    priorities: Vec<(usize, Specificity)>,
+    weight: impl Fn(usize) -> f32,
+) -> Vec<(usize, Specificity)> {
+    let mut weighted: Vec<(isize, (usize, Specificity))> = priorities
+        .into_iter()
+        .map(|(index, priority)| {
+            (
+                priority.q.saturating_mul(server_quality(weight(index))),
+                (index, priority),
+            )
+        })
+        .filter(|&(q, _)| q > 0)
+        .collect();
+    weighted.sort_by(|(a_q, (a_index, a)), (b_q, (b_index, b))| {
+        b_q.cmp(a_q)
+            .then_with(|| b.s.cmp(&a.s))
+            .then_with(|| a_index.cmp(b_index))
+    });
+
+    weighted.into_iter().map(|(_, priority)| priority).collect()
+}
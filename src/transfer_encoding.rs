@@ -0,0 +1,98 @@
+//! TE negotiation.
+//!
+//! The TE header lists the transfer codings a client accepts, with the same
+//! grammar as Accept-Encoding but without `*`. Two names are special, as
+//! RFC 9110 section 10.1.4 describes: `chunked` is always acceptable, so
+//! the header can't exclude it, and `trailers` is not a coding but says the
+//! client accepts trailer fields, which [`accepts_trailers`] tells.
+//!
+//! `chunked` is negotiated as if the header ended with it, at the lowest
+//! quality the header gives any coding, so the codings the client names are
+//! preferred to it. Otherwise codings are ordered as in
+//! [Accept-Encoding](mod@crate::encoding), aliases included.
+
+use crate::encoding::canonical_name;
+use crate::options::{HeaderKind, Limits, NegotiationOptions};
+use crate::tokens::{self, AcceptToken, TokenOptions};
+
+/// TE has no wildcard.
+const OPTIONS: TokenOptions = TokenOptions {
+    wildcard: false,
+    kind: HeaderKind::Te,
+    negotiation: NegotiationOptions::DEFAULT,
+};
+
+/// The transfer coding every HTTP/1.1 recipient accepts.
+const CHUNKED: &str = "chunked";
+
+/// The TE entry accepting trailer fields, which is not a coding.
+const TRAILERS: &str = "trailers";
+
+/// Parse a TE header into its codings, with `chunked` added after them.
+fn parse<'a>(te: &'a str, limits: &Limits) -> Vec<AcceptToken<'a>> {
+    let mut accepts = tokens::parse_tokens_within(te, limits);
+    accepts.retain(|accept| {
+        !accept.token().eq_ignore_ascii_case(TRAILERS)
+            && !accept.token().eq_ignore_ascii_case(CHUNKED)
+    });
+    tokens::canonicalize_tokens(&mut accepts, canonical_name);
+    tokens::imply_token(&mut accepts, CHUNKED, OPTIONS.wildcard);
+    accepts
+}
+
+/// Get the preferred transfer codings from a TE header.
+///
+/// A missing header accepts only `chunked`.
+pub fn preferred(te: Option<&str>, provided: &[&str]) -> Vec<String> {
+    preferred_with_quality(te, provided)
+        .into_iter()
+        .map(|(coding, _)| coding)
+        .collect()
+}
+
+/// Get the preferred transfer codings from a TE header along with their quality.
+///
+/// `chunked` has the quality it is negotiated at, the lowest the header gives a coding.
+pub fn preferred_with_quality(te: Option<&str>, provided: &[&str]) -> Vec<(String, f32)> {
+    let accepts = parse(te.unwrap_or(""), &OPTIONS.negotiation.limits);
+    let names: Vec<&str> = provided
+        .iter()
+        .map(|&coding| canonical_name(coding).unwrap_or(coding))
+        .collect();
+
+    tokens::preferred_parsed_priorities(
+        &accepts,
+        &names,
+        OPTIONS.wildcard,
+        &OPTIONS,
+        tokens::compare_provided_order,
+    )
+    .into_iter()
+    .map(|(index, priority)| {
+        (
+            OPTIONS.negotiation.output(provided[index]),
+            priority.quality(),
+        )
+    })
+    .collect()
+}
+
+/// Get the most preferred transfer coding from a TE header.
+///
+/// This is only `None` if `chunked` isn't provided and the header accepts
+/// none of the provided codings.
+pub fn most_preferred(te: Option<&str>, provided: &[&str]) -> Option<String> {
+    preferred(te, provided).into_iter().next()
+}
+
+/// Check if a TE header says the client accepts trailer fields.
+pub fn accepts_trailers(te: Option<&str>) -> bool {
+    let te = match te {
+        Some(te) => te,
+        None => return false,
+    };
+
+    tokens::parse_tokens_within(te, &OPTIONS.negotiation.limits)
+        .iter()
+        .any(|accept| accept.token().eq_ignore_ascii_case(TRAILERS))
+}
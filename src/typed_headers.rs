@@ -0,0 +1,31 @@
+//! Typed negotiation headers for the `headers` crate.
+
+use std::iter;
+
+use headers::{Error, Header, HeaderName, HeaderValue};
+
+use crate::charset::AcceptCharsetHeader;
+
+impl Header for AcceptCharsetHeader {
+    fn name() -> &'static HeaderName {
+        &http::header::ACCEPT_CHARSET
+    }
+
+    fn decode<'i, I>(values: &mut I) -> Result<Self, Error>
+    where
+        I: Iterator<Item = &'i HeaderValue>,
+    {
+        let values = values
+            .map(|value| value.to_str())
+            .collect::<Result<Vec<&str>, _>>()
+            .map_err(|_| Error::invalid())?;
+
+        Ok(AcceptCharsetHeader::parse(&values.join(", ")))
+    }
+
+    fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
+        if let Ok(value) = HeaderValue::from_str(&self.to_string()) {
+            values.extend(iter::once(value));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+#[test]
+fn negotiator_charset_batch() {
+    let headers = vec![
+        None,
+        Some("ISO-8859-1"),
+        Some("ISO-8859-1;q=0.5, UTF-8"),
+        Some("KOI8-R"),
+        Some("utf-8"),
+        Some("*, UTF-8;q=0"),
+    ];
+
+    let summary = negotiator::charset::batch(headers, &["UTF-8", "ISO-8859-1", "UTF-8"]);
+    assert_eq!(
+        summary.selected,
+        vec![("UTF-8".to_string(), 3), ("ISO-8859-1".to_string(), 2)]
+    );
+    assert_eq!(summary.not_acceptable, 1);
+}
+
+#[test]
+fn negotiator_charset_batch_agrees_with_charset() {
+    let headers = [
+        None,
+        Some("*"),
+        Some("UTF-8;q=0.8, ISO-8859-1"),
+        Some("KOI8-R;q=0"),
+        Some("windows-1252, KOI8-R;q=0.9"),
+    ];
+    let provided = ["UTF-8", "ISO-8859-1", "KOI8-R", "windows-1252"];
+
+    let summary = negotiator::charset::batch(headers.iter().copied(), &provided);
+
+    for (charset, count) in &summary.selected {
+        let expected = headers
+            .iter()
+            .filter(|header| {
+                negotiator::charset::most_preferred(**header, &provided).as_ref() == Some(charset)
+            })
+            .count();
+        assert_eq!(*count, expected, "charset: {:?}", charset);
+    }
+    let expected = headers
+        .iter()
+        .filter(|header| negotiator::charset::most_preferred(**header, &provided).is_none())
+        .count();
+    assert_eq!(summary.not_acceptable, expected);
+}
+
+#[test]
+fn negotiator_token_batch_empty_provided() {
+    let headers = vec![Some("sha-256"), None];
+
+    let summary =
+        negotiator::preferred_token_batch(headers, &[], &negotiator::TokenOptions::default());
+    assert_eq!(summary.selected, vec![]);
+    assert_eq!(summary.not_acceptable, 2);
+}
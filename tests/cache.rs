@@ -0,0 +1,129 @@
+#![cfg(feature = "cache")]
+
+use std::sync::Arc;
+use std::thread;
+
+use negotiator::cache::CachedNegotiator;
+
+const CHARSETS: &[&str] = &["UTF-8", "ISO-8859-1", "KOI8-R"];
+const CODINGS: &[&str] = &["br", "gzip", "identity"];
+
+#[test]
+fn cache_hit_and_miss() {
+    let cache = CachedNegotiator::charsets(CHARSETS, 4);
+    assert!(cache.is_empty());
+    assert_eq!(cache.provided(), CHARSETS);
+    assert_eq!(cache.capacity(), 4);
+
+    let header = Some("ISO-8859-1, UTF-8;q=0.5");
+    let missed = cache.preferred(header);
+    assert_eq!(*missed, [1, 0]);
+    assert_eq!(cache.len(), 1);
+
+    // A hit shares the cached result.
+    let hit = cache.preferred(header);
+    assert!(Arc::ptr_eq(&missed, &hit));
+    assert_eq!(cache.len(), 1);
+
+    // A missing header isn't cached, but always gets the same result.
+    assert_eq!(*cache.preferred(None), [0, 1, 2]);
+    assert!(Arc::ptr_eq(&cache.preferred(None), &cache.preferred(None)));
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn cache_eviction() {
+    let cache = CachedNegotiator::encodings(CODINGS, 2);
+
+    let gzip = cache.preferred(Some("gzip"));
+    let br = cache.preferred(Some("br"));
+    // Using gzip again leaves br as the least recently used.
+    assert!(Arc::ptr_eq(&gzip, &cache.preferred(Some("gzip"))));
+    cache.preferred(Some("gzip, br"));
+    assert_eq!(cache.len(), 2);
+
+    assert!(Arc::ptr_eq(&gzip, &cache.preferred(Some("gzip"))));
+    assert!(!Arc::ptr_eq(&br, &cache.preferred(Some("br"))));
+    assert_eq!(cache.len(), 2);
+
+    // Without a capacity nothing is cached.
+    let uncached = CachedNegotiator::encodings(CODINGS, 0);
+    assert_eq!(*uncached.preferred(Some("gzip")), [1, 2]);
+    assert!(uncached.is_empty());
+}
+
+#[test]
+fn cache_huge_header() {
+    let cache = CachedNegotiator::charsets(CHARSETS, 4);
+    let header = format!(
+        "{}, UTF-8",
+        "x".repeat(negotiator::Limits::default().max_header_len)
+    );
+
+    assert_eq!(
+        *cache.preferred(Some(&header)),
+        *negotiator::charset::preferred_indices(Some(&header), CHARSETS)
+    );
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn cache_matches_uncached() {
+    let charsets = CachedNegotiator::charsets(CHARSETS, 3);
+    let codings = CachedNegotiator::encodings(CODINGS, 3);
+    let headers = [
+        None,
+        Some(""),
+        Some("*"),
+        Some("utf-8, iso-8859-1;q=0.5"),
+        Some("KOI8-R;q=0, *;q=0.1"),
+        Some("gzip, deflate, br"),
+        Some("br;q=1.0, gzip;q=0.8, *;q=0.1"),
+        Some("identity;q=0"),
+    ];
+
+    // More headers than fit, several times round, so results come from misses, hits and evictions.
+    for header in headers
+        .iter()
+        .chain(headers.iter())
+        .chain(headers.iter().rev())
+    {
+        assert_eq!(
+            *charsets.preferred(*header),
+            *negotiator::charset::preferred_indices(*header, CHARSETS),
+            "header: {:?}",
+            header
+        );
+        assert_eq!(
+            *codings.preferred(*header),
+            *negotiator::encoding::preferred_indices(*header, CODINGS),
+            "header: {:?}",
+            header
+        );
+    }
+}
+
+#[test]
+fn cache_concurrent() {
+    let cache = Arc::new(CachedNegotiator::encodings(CODINGS, 2));
+    let headers = ["gzip", "br, gzip", "identity;q=0, *", "br;q=0.5, gzip"];
+
+    let threads: Vec<_> = (0..4)
+        .map(|t| {
+            let cache = cache.clone();
+            thread::spawn(move || {
+                for i in 0..200 {
+                    let header = headers[(t + i) % headers.len()];
+                    assert_eq!(
+                        *cache.preferred(Some(header)),
+                        *negotiator::encoding::preferred_indices(Some(header), CODINGS)
+                    );
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert!(cache.len() <= 2);
+}
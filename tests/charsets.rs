@@ -4,7 +4,7 @@ macro_rules! assert_negotiate {
         match ($header, $accepted, $expected) {
             (header, accepted, expected) => {
                 assert_eq!(
-                    negotiator::charset(header, accepted),
+                    negotiator::charset::most_preferred(header, accepted),
                     Some(expected.to_string()),
                     "header: {:?}, wanted: {:?}, expected: {:?}",
                     header,
@@ -21,12 +21,12 @@ macro_rules! assert_negotiate_none {
         match ($header, $accepted) {
             (header, accepted) => {
                 assert_eq!(
-                    negotiator::charset(header, accepted),
+                    negotiator::charset::most_preferred(header, accepted),
                     None,
                     "header: {:?}, wanted: {:?}, expected: {:?}",
                     header,
                     accepted,
-                    None
+                    None::<String>
                 ); 
             }
         }
@@ -35,44 +35,39 @@ macro_rules! assert_negotiate_none {
 
 #[test]
 fn negotiator_charset () {
-    assert_negotiate!(None, &[], "*");
+    assert_negotiate_none!(None, &[]);
+    assert_negotiate_none!(Some("*, UTF-8;"), &[]);
+    assert_negotiate_none!(Some("ISO-8859-1"), &[]);
 
+    // Upstream's charset(), which lists the header.
+    let most_acceptable = |header| negotiator::acceptable_charsets(header).into_iter().next();
+    assert_eq!(most_acceptable(None), Some("*".to_string()));
+    assert_eq!(most_acceptable(Some("*, UTF-8;")), Some("*".to_string()));
+    assert_eq!(most_acceptable(Some("*, UTF-8;q=0")), Some("*".to_string()));
     assert_eq!(
-        negotiator::charset(None, &[]),
-        Some("*".to_string()),
-        "header: (none), wanted: (none), expected: \"*\""
+        most_acceptable(Some("ISO-8859-1")),
+        Some("ISO-8859-1".to_string())
     );
-
-    assert_negotiate!(Some("*, UTF-8;"), &[], "*");
-
+    assert_eq!(most_acceptable(Some("UTF-8;q=0")), None);
+    assert_eq!(most_acceptable(Some("UTF-8")), Some("UTF-8".to_string()));
     assert_eq!(
-        negotiator::charset(Some("*, UTF-8;"), &[]),
-        Some("*".to_string()),
-        "header: \"*, UTF-8;\", wanted: (none), expected: \"*\""
+        most_acceptable(Some("UTF-8;q=0.8, ISO-8859-1")),
+        Some("ISO-8859-1".to_string())
     );
-    
-    assert_negotiate!(Some("*, UTF-8;q=0"), &[], "*");
-
     assert_eq!(
-        negotiator::charset(Some("*, UTF-8;q=0"), &[]),
-        Some("*".to_string()),
-        "header: \"*, UTF-8;\", wanted: (none), expected: \"*\""
+        most_acceptable(Some("UTF-8;q=0.9, ISO-8859-1;q=0.8, UTF-8;q=0.7")),
+        Some("UTF-8".to_string())
     );
 
-    assert_negotiate!(Some("ISO-8859-1"), &[], "ISO-8859-1");
-    // assert_negotiate_none!(Some("UTF-8;q=0"), &[]);
-    assert_negotiate!(Some("UTF-8"), &[], "UTF-8");
-    assert_negotiate!(Some("UTF-8;q=0.8, ISO-8859-1"), &[], "ISO-8859-1");
-    assert_negotiate!(Some("UTF-8;q=0.9, ISO-8859-1;q=0.8, UTF-8;q=0.7"), &[], "UTF-8");
     assert_negotiate!(None, &["UTF-8"], "UTF-8");
     assert_negotiate!(None, &["UTF-8", "ISO-8859-1"], "UTF-8");
-    // assert_negotiate_none!(Some("*"), &[]);
+    assert_negotiate_none!(Some("*"), &[]);
     assert_negotiate!(Some("*"), &["UTF-8"], "UTF-8");
     assert_negotiate!(Some("*"), &["UTF-8", "ISO-8859-1"], "UTF-8");
     assert_negotiate!(Some("*, UTF-8"), &["UTF-8"], "UTF-8");
     assert_negotiate!(Some("*, UTF-8"), &["UTF-8", "ISO-8859-1"], "UTF-8");
     assert_negotiate!(Some("*, UTF-8;q=0"), &["UTF-8", "ISO-8859-1"], "ISO-8859-1");
-    // assert_negotiate_none!(Some("*, UTF-8;q=0"), &["UTF-8"]);
+    assert_negotiate_none!(Some("*, UTF-8;q=0"), &["UTF-8"]);
     assert_negotiate!(Some("ISO-8859-1"), &["ISO-8859-1"], "ISO-8859-1");
     assert_negotiate!(Some("ISO-8859-1"), &["UTF-8", "ISO-8859-1"], "ISO-8859-1");
 }
@@ -299,3 +294,1276 @@ fn negotiator_charset () {
 
 //   return request
 // }
+
+#[test]
+fn negotiator_charset_where() {
+    use std::cell::Cell;
+
+    let calls = Cell::new(0);
+    let result = negotiator::charset::preferred_where(None, &["UTF-8", "ISO-8859-1", "KOI8-R"], |_| {
+        calls.set(calls.get() + 1);
+        true
+    });
+    assert_eq!(result, Some("UTF-8".to_string()));
+    assert_eq!(calls.get(), 1, "should stop at the first passing charset");
+
+    let calls = Cell::new(0);
+    let result = negotiator::charset::preferred_where(None, &["UTF-8", "ISO-8859-1", "KOI8-R"], |c| {
+        calls.set(calls.get() + 1);
+        c != "UTF-8"
+    });
+    assert_eq!(result, Some("ISO-8859-1".to_string()));
+    assert_eq!(calls.get(), 2);
+
+    let calls = Cell::new(0);
+    let result = negotiator::charset::preferred_where(Some("ISO-8859-1,UTF-8"), &["UTF-8", "ISO-8859-1"], |c| {
+        calls.set(calls.get() + 1);
+        c != "ISO-8859-1"
+    });
+    assert_eq!(result, Some("UTF-8".to_string()));
+    assert_eq!(calls.get(), 2);
+
+    let calls = Cell::new(0);
+    let result = negotiator::charset::preferred_where(Some("UTF-8"), &["ISO-8859-1", "UTF-8"], |c| {
+        calls.set(calls.get() + 1);
+        assert_eq!(c, "UTF-8", "unacceptable charsets must not reach the predicate");
+        true
+    });
+    assert_eq!(result, Some("UTF-8".to_string()));
+    assert_eq!(calls.get(), 1);
+
+    let calls = Cell::new(0);
+    let result = negotiator::charset::preferred_where(Some("*"), &["UTF-8", "ISO-8859-1"], |_| {
+        calls.set(calls.get() + 1);
+        false
+    });
+    assert_eq!(result, None);
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn negotiator_charsets_duplicate_provided() {
+    assert_eq!(
+        negotiator::charset::preferred(None, &["UTF-8", "UTF-8"]),
+        vec!["UTF-8".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::preferred(Some("*"), &["UTF-8", "ISO-8859-1", "UTF-8", "ISO-8859-1"]),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::most_preferred(Some("UTF-8"), &["utf-8", "UTF-8"]),
+        Some("utf-8".to_string())
+    );
+
+    let calls = std::cell::Cell::new(0);
+    let result = negotiator::charset::preferred_where(None, &["UTF-8", "UTF-8", "KOI8-R"], |c| {
+        calls.set(calls.get() + 1);
+        c == "KOI8-R"
+    });
+    assert_eq!(result, Some("KOI8-R".to_string()));
+    assert_eq!(calls.get(), 2, "duplicates should only be offered once");
+
+    // Lowercased output would repeat itself, so case is ignored.
+    let normalize = negotiator::NegotiationOptions {
+        normalize_case: true,
+        ..negotiator::NegotiationOptions::default()
+    };
+    assert_eq!(
+        negotiator::charset::preferred_with_options(Some("utf-8"), &["ISO-8859-1", "Utf-8", "UTF-8"], &normalize),
+        vec!["utf-8".to_string()]
+    );
+}
+
+#[test]
+fn negotiator_charsets_provided_casing() {
+    let header = Some("ISO-8859-1");
+    let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(negotiator::charset(header, &["iso-8859-1"]), Some("iso-8859-1".to_string()));
+    assert_eq!(negotiator::charset(header, &["iso-8859-1", "ISO-8859-1"]), Some("iso-8859-1".to_string()));
+    assert_eq!(negotiator::charset(header, &["ISO-8859-1", "iso-8859-1"]), Some("ISO-8859-1".to_string()));
+    assert_eq!(negotiator::charset(header, &["utf-8"]), None);
+
+    assert_eq!(negotiator::charsets(header, &["iso-8859-1"]), strings(&["iso-8859-1"]));
+    assert_eq!(
+        negotiator::charsets(header, &["iso-8859-1", "ISO-8859-1"]),
+        strings(&["iso-8859-1", "ISO-8859-1"])
+    );
+    assert_eq!(
+        negotiator::charsets(header, &["ISO-8859-1", "iso-8859-1"]),
+        strings(&["ISO-8859-1", "iso-8859-1"])
+    );
+    assert_eq!(
+        negotiator::charsets(Some("utf-8;q=0.5, ISO-8859-1"), &["Utf-8", "iso-8859-1", "UTF-8"]),
+        strings(&["iso-8859-1", "Utf-8", "UTF-8"])
+    );
+    assert!(negotiator::charsets(header, &["utf-8"]).is_empty());
+}
+
+#[test]
+fn negotiator_charset_module() {
+    let ranges = negotiator::charset::parse("UTF-8;q=0.5, *;q=0.1, KOI8-R;q=0");
+    let tokens: Vec<&str> = ranges.iter().map(|range| range.token()).collect();
+    assert_eq!(tokens, vec!["UTF-8", "*", "KOI8-R"]);
+
+    assert_eq!(
+        negotiator::charset::preferred_with_quality(Some("UTF-8;q=0.5"), &["UTF-8"]),
+        vec![("UTF-8".to_string(), 0.5)]
+    );
+    assert_eq!(
+        negotiator::charset::preferred_with_quality(None, &["UTF-8"]),
+        vec![("UTF-8".to_string(), 1.0)]
+    );
+
+    assert!(negotiator::charset::is_acceptable(None, "UTF-8"));
+    assert!(negotiator::charset::is_acceptable(Some("*"), "UTF-8"));
+    assert!(negotiator::charset::is_acceptable(Some("utf-8"), "UTF-8"));
+    assert!(!negotiator::charset::is_acceptable(Some("ISO-8859-1"), "UTF-8"));
+    assert!(!negotiator::charset::is_acceptable(Some("UTF-8;q=0"), "UTF-8"));
+}
+
+#[test]
+fn negotiator_charset_compat() {
+    let header = Some("ISO-8859-1, UTF-8;q=0.5");
+    let available = &["UTF-8", "ISO-8859-1"];
+
+    assert_eq!(
+        negotiator::charset(header, available),
+        negotiator::charset::most_preferred(header, available)
+    );
+    assert_eq!(
+        negotiator::charsets(header, available),
+        negotiator::charset::preferred(header, available)
+    );
+    assert_eq!(
+        negotiator::charset_where(header, available, |c| c == "UTF-8"),
+        negotiator::charset::preferred_where(header, available, |c| c == "UTF-8")
+    );
+    assert_eq!(
+        negotiator::charset_batch(vec![header], available),
+        negotiator::charset::batch(vec![header], available)
+    );
+
+    let headers: &[(&str, &str)] = &[("Accept-Charset", "ISO-8859-1, UTF-8;q=0.5")];
+    assert_eq!(
+        negotiator::charset_from_headers(headers, available),
+        negotiator::charset(header, available)
+    );
+    assert_eq!(
+        negotiator::charsets_from_headers(headers, available),
+        negotiator::charsets(header, available)
+    );
+}
+
+#[test]
+fn negotiator_accept_charset_header() {
+    use negotiator::charset::AcceptCharsetHeader;
+
+    let header = AcceptCharsetHeader::parse("UTF-8, ISO-8859-1;q=0.5, KOI8-R;q=0");
+    assert_eq!(header.entries().len(), 3);
+    assert_eq!(header.to_string(), "UTF-8, ISO-8859-1;q=0.5, KOI8-R;q=0");
+    assert_eq!(
+        header.preferred(&["KOI8-R", "ISO-8859-1", "UTF-8"]),
+        negotiator::charset::preferred(
+            Some("UTF-8, ISO-8859-1;q=0.5, KOI8-R;q=0"),
+            &["KOI8-R", "ISO-8859-1", "UTF-8"]
+        )
+    );
+
+    let reparsed = AcceptCharsetHeader::parse(&header.to_string());
+    assert_eq!(reparsed.to_string(), header.to_string());
+
+    assert_eq!(
+        AcceptCharsetHeader::parse("utf-8;q=1.000, *;q=0.125").to_string(),
+        "utf-8, *;q=0.125"
+    );
+}
+
+#[test]
+fn negotiator_charsets_normalize_case() {
+    use negotiator::charset::{self, AcceptCharsetHeader};
+    use negotiator::NegotiationOptions;
+
+    let header = Some("utf-8, ISO-8859-1;q=0.8");
+    let provided = ["ISO-8859-1", "UTF-8"];
+    let normalize = NegotiationOptions {
+        normalize_case: true,
+        ..NegotiationOptions::default()
+    };
+
+    assert_eq!(
+        charset::preferred_with_options(header, &provided, &NegotiationOptions::default()),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        charset::preferred_with_options(header, &provided, &normalize),
+        vec!["utf-8".to_string(), "iso-8859-1".to_string()]
+    );
+    assert_eq!(
+        charset::acceptable_with_options(Some("Shift_JIS, UTF-8"), &normalize),
+        vec!["shift_jis".to_string(), "utf-8".to_string()]
+    );
+
+    let parsed = AcceptCharsetHeader::parse("utf-8, ISO-8859-1;q=0.8");
+    assert_eq!(
+        parsed.preferred_with_quality(&provided),
+        vec![("UTF-8".to_string(), 1.0), ("ISO-8859-1".to_string(), 0.8)]
+    );
+    assert_eq!(
+        parsed.with_options(normalize).preferred_with_quality(&provided),
+        vec![("utf-8".to_string(), 1.0), ("iso-8859-1".to_string(), 0.8)]
+    );
+}
+
+#[test]
+fn negotiator_charsets_on_unmatched() {
+    use negotiator::charset::{self, AcceptCharsetHeader};
+    use negotiator::{HeaderKind, NegotiationOptions};
+    use std::sync::{Arc, Mutex};
+
+    let unmatched = Arc::new(Mutex::new(Vec::new()));
+    let seen = unmatched.clone();
+    let options = NegotiationOptions {
+        on_unmatched: Some(Arc::new(move |kind: HeaderKind, token: &str| {
+            seen.lock().unwrap().push((kind, token.to_string()))
+        })),
+        ..NegotiationOptions::default()
+    };
+
+    let header = "UTF-8, KOI8-R;q=0, ISO-8859-5;q=0.5, *;q=0.1, Shift_JIS";
+    assert_eq!(
+        charset::preferred_with_options(Some(header), &["utf-8", "ISO-8859-1"], &options),
+        vec!["utf-8".to_string(), "ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        *unmatched.lock().unwrap(),
+        vec![
+            (HeaderKind::AcceptCharset, "ISO-8859-5".to_string()),
+            (HeaderKind::AcceptCharset, "Shift_JIS".to_string()),
+        ]
+    );
+
+    unmatched.lock().unwrap().clear();
+    AcceptCharsetHeader::parse("UTF-8, ISO-8859-1")
+        .with_options(options.clone())
+        .preferred(&["UTF-8", "ISO-8859-1"]);
+    charset::preferred_with_options(None, &["UTF-8"], &options);
+    charset::preferred_with_options(Some(header), &[], &options);
+    assert!(unmatched.lock().unwrap().is_empty());
+}
+
+#[test]
+fn negotiator_charsets_ascii_case_insensitive() {
+    assert_eq!(
+        negotiator::charset::preferred(Some("utf-8, Iso-8859-1;q=0.5"), &["ISO-8859-1", "UTF-8"]),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::preferred(Some("UTF-8"), &["utf-8", "KOI8-R", "Utf-8"]),
+        vec!["utf-8".to_string(), "Utf-8".to_string()]
+    );
+
+    // Only ASCII letters are folded.
+    assert_eq!(negotiator::charset::most_preferred(Some("ÄBC"), &["äbc"]), None);
+}
+
+#[test]
+fn negotiator_charsets_wildcard_fast_path() {
+    let provided: &[&[&str]] = &[
+        &[],
+        &["UTF-8"],
+        &["UTF-8", "ISO-8859-1"],
+        &["ISO-8859-1", "utf-8", "UTF-8"],
+    ];
+
+    for provided in provided {
+        // `*;q=1` means the same as `*` but is negotiated normally.
+        let slow = negotiator::charset::preferred_with_quality(Some("*;q=1"), provided);
+        for header in &[None, Some("*"), Some(" * ")] {
+            assert_eq!(negotiator::charset::preferred_with_quality(*header, provided), slow);
+            assert_eq!(
+                negotiator::charset::most_preferred(*header, provided),
+                negotiator::charset::most_preferred(Some("*;q=1"), provided)
+            );
+            assert_eq!(
+                negotiator::charset::batch(vec![*header], provided),
+                negotiator::charset::batch(vec![Some("*;q=1")], provided)
+            );
+        }
+
+        assert!(negotiator::charset::preferred(Some("*;q=0"), provided).is_empty());
+    }
+}
+
+#[test]
+fn negotiator_charsets_exact_beats_later_wildcard() {
+    assert_eq!(
+        negotiator::charsets(Some("*, UTF-8;q=0"), &["UTF-8", "ISO-8859-1"]),
+        vec!["ISO-8859-1".to_string()]
+    );
+    assert!(negotiator::charsets(Some("*, UTF-8;q=0"), &["UTF-8"]).is_empty());
+    assert!(negotiator::charsets(Some("*;q=0.5, utf-8;q=0"), &["UTF-8"]).is_empty());
+    assert_eq!(
+        negotiator::charset::preferred(Some("ISO-8859-1;q=0, *;q=0.8"), &["ISO-8859-1", "UTF-8"]),
+        vec!["UTF-8".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::preferred_with_quality(
+            Some("KOI8-R;q=0.001, KOI8-R;q=0.8, *"),
+            &["KOI8-R"]
+        ),
+        vec![("KOI8-R".to_string(), 0.8)]
+    );
+}
+
+#[test]
+fn negotiator_charsets_parameter_edge_cases() {
+    let quality = |header: &str| negotiator::charset::parse(header)[0].quality();
+
+    // Empty parameters and a q without a value leave the default quality.
+    assert_eq!(quality("UTF-8;q"), 1.0);
+    assert_eq!(quality("UTF-8;;q=0.5;"), 0.5);
+
+    // An unparsable q skips the entry.
+    for header in &[
+        "UTF-8;q=",
+        "UTF-8;q=0.5=1",
+        "UTF-8;q=abc",
+        "UTF-8;q=0.5;q=high",
+    ] {
+        assert!(
+            negotiator::charset::parse(header).is_empty(),
+            "{:?}",
+            header
+        );
+    }
+
+    // The last q wins, other parameters are ignored.
+    assert_eq!(quality("UTF-8;q=0.5;q=0.2"), 0.2);
+    assert_eq!(quality("UTF-8;level=1;q=0.3;x"), 0.3);
+}
+
+#[test]
+fn negotiator_most_preferred_matches_preferred() {
+    let headers = [
+        None,
+        Some(""),
+        Some("*"),
+        Some("*, UTF-8"),
+        Some("*, UTF-8;q=0"),
+        Some("*;q=0"),
+        Some("ISO-8859-1"),
+        Some("ISO-8859-1, UTF-8;q=0.5"),
+        Some("ISO-8859-1;q=0, *;q=0.8"),
+        Some("KOI8-R;q=0.001, KOI8-R;q=0.8, *"),
+        Some("UTF-8, ISO-8859-1;q=0.5, KOI8-R;q=0"),
+        Some("UTF-8;q=0.8, ISO-8859-1"),
+        Some("UTF-8;q=0.9, ISO-8859-1;q=0.8, UTF-8;q=0.7"),
+        Some("utf-8, Iso-8859-1;q=0.5, *;q=0.1"),
+        Some("UTF-8;q=0.5, ISO-8859-1;q=0.5, KOI8-R;q=0.5"),
+    ];
+    let provided: &[&[&str]] = &[
+        &[],
+        &["UTF-8"],
+        &["ISO-8859-1"],
+        &["UTF-8", "ISO-8859-1"],
+        &["KOI8-R", "ISO-8859-1", "UTF-8"],
+        &["utf-8", "UTF-8", "KOI8-R"],
+        &["SHIFT_JIS"],
+    ];
+
+    for header in &headers {
+        for provided in provided {
+            assert_eq!(
+                negotiator::charset::most_preferred(*header, provided),
+                negotiator::charset::preferred(*header, provided)
+                    .into_iter()
+                    .next(),
+                "{:?} against {:?}",
+                header,
+                provided
+            );
+        }
+    }
+}
+
+#[test]
+fn negotiator_charsets_fractional_quality() {
+    assert_eq!(
+        negotiator::charset::most_preferred(Some("UTF-8;q=0.8, ISO-8859-1;q=0.9"), &["UTF-8", "ISO-8859-1"]),
+        Some("ISO-8859-1".to_string())
+    );
+    assert_eq!(
+        negotiator::charset::preferred(Some("UTF-8;q=0.5, ISO-8859-1;q=0.501"), &["UTF-8", "ISO-8859-1"]),
+        vec!["ISO-8859-1".to_string(), "UTF-8".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::preferred_with_quality(Some("UTF-8;q=0.501, ISO-8859-1;q=0.5"), &["ISO-8859-1", "UTF-8"]),
+        vec![("UTF-8".to_string(), 0.501), ("ISO-8859-1".to_string(), 0.5)]
+    );
+    assert_eq!(
+        negotiator::charset::preferred(Some("UTF-8;q=0, ISO-8859-1;q=0.001"), &["UTF-8", "ISO-8859-1"]),
+        vec!["ISO-8859-1".to_string()]
+    );
+}
+
+#[test]
+fn negotiator_charsets_bare_tokens() {
+    let raw = " UTF-8;q=0.5,  ISO-8859-1 ;level=1, \tKOI8-R;q=0.2 ";
+    let header = Some(raw);
+    assert_eq!(
+        negotiator::charset::acceptable(header),
+        vec![
+            "ISO-8859-1".to_string(),
+            "UTF-8".to_string(),
+            "KOI8-R".to_string()
+        ]
+    );
+    assert_eq!(
+        negotiator::charset::preferred(header, &["utf-8", "koi8-r"]),
+        vec!["utf-8".to_string(), "koi8-r".to_string()]
+    );
+
+    let tokens: Vec<String> = negotiator::charset::parse(raw)
+        .iter()
+        .map(|range| range.token().to_string())
+        .collect();
+    assert_eq!(tokens, vec!["UTF-8", "ISO-8859-1", "KOI8-R"]);
+}
+
+#[test]
+fn negotiator_charsets_duplicate_entries() {
+    let header = Some("UTF-8;q=0.9, ISO-8859-1;q=0.8, UTF-8;q=0.7");
+    assert_eq!(
+        negotiator::charset::acceptable(header),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::preferred_with_quality(header, &["ISO-8859-1", "UTF-8"]),
+        vec![("UTF-8".to_string(), 0.9), ("ISO-8859-1".to_string(), 0.8)]
+    );
+    assert_eq!(
+        negotiator::charset::acceptable(Some("UTF-8;q=0, utf-8;q=0.5, ISO-8859-1;q=0.4")),
+        vec!["utf-8".to_string(), "ISO-8859-1".to_string()]
+    );
+
+    // Among equal qualities, the first entry decides the order.
+    let header = Some("KOI8-R;q=0.5, UTF-8;q=0.5, koi8-r;q=0.5");
+    assert_eq!(
+        negotiator::charset::acceptable(header),
+        vec!["KOI8-R".to_string(), "UTF-8".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::preferred(header, &["UTF-8", "KOI8-R"]),
+        vec!["KOI8-R".to_string(), "UTF-8".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::most_preferred(header, &["UTF-8", "KOI8-R"]),
+        Some("KOI8-R".to_string())
+    );
+}
+
+#[test]
+fn negotiator_charsets_index_order_no_underflow() {
+    assert_eq!(
+        negotiator::charsets(Some("ISO-8859-1;q=0.5, KOI8-R"), &["KOI8-R", "UTF-8", "ISO-8859-1"]),
+        vec!["KOI8-R".to_string(), "ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        negotiator::charsets(Some("KOI8-R, ISO-8859-1, UTF-8"), &["UTF-8", "ISO-8859-1", "KOI8-R"]),
+        vec!["KOI8-R".to_string(), "ISO-8859-1".to_string(), "UTF-8".to_string()]
+    );
+    assert_eq!(
+        negotiator::charsets(Some("*"), &["KOI8-R", "UTF-8", "ISO-8859-1", "Shift_JIS"]),
+        vec!["KOI8-R".to_string(), "UTF-8".to_string(), "ISO-8859-1".to_string(), "Shift_JIS".to_string()]
+    );
+}
+
+#[test]
+fn negotiator_charsets_tied_priorities() {
+    let provided = ["UTF-8", "ISO-8859-1", "KOI8-R"];
+    let expected = vec!["UTF-8".to_string(), "ISO-8859-1".to_string(), "KOI8-R".to_string()];
+
+    // Each provided charset is matched by the same entry at the same quality.
+    assert_eq!(negotiator::charsets(Some("*"), &provided), expected);
+    assert_eq!(negotiator::charsets(Some("*;q=0.5"), &provided), expected);
+    assert_eq!(negotiator::charset::batch(vec![Some("*;q=0.5")], &provided).selected[0], ("UTF-8".to_string(), 1));
+}
+
+#[test]
+fn negotiator_charsets_with_weights() {
+    let header = Some("UTF-8;q=0.5, ISO-8859-1;q=0.8, *;q=0.1, KOI8-R;q=0");
+    let available = &["UTF-8", "KOI8-R", "ISO-8859-1", "Shift_JIS"];
+
+    assert_eq!(
+        negotiator::charsets_with_weights(header, available),
+        vec![
+            ("ISO-8859-1".to_string(), 0.8),
+            ("UTF-8".to_string(), 0.5),
+            ("Shift_JIS".to_string(), 0.1),
+        ]
+    );
+    assert_eq!(
+        negotiator::charsets_with_weights(header, available)
+            .into_iter()
+            .map(|(charset, _)| charset)
+            .collect::<Vec<_>>(),
+        negotiator::charsets(header, available)
+    );
+    assert_eq!(
+        negotiator::charset_with_weight(header, available),
+        Some(("ISO-8859-1".to_string(), 0.8))
+    );
+    assert_eq!(negotiator::charset_with_weight(None, available), Some(("UTF-8".to_string(), 1.0)));
+    assert_eq!(negotiator::charset_with_weight(Some("*"), &["KOI8-R"]), Some(("KOI8-R".to_string(), 1.0)));
+    assert_eq!(negotiator::charset_with_weight(Some("*, KOI8-R;q=0"), &["KOI8-R"]), None);
+}
+
+#[test]
+fn negotiator_charset_indices() {
+    let headers = [
+        None,
+        Some("*"),
+        Some("*, UTF-8;q=0"),
+        Some("utf-8, iso-8859-1;q=0.5"),
+        Some("KOI8-R;q=0.5, UTF-8;q=0.8, *;q=0.1"),
+        Some("UTF-8;q=0"),
+        Some("Shift_JIS"),
+    ];
+    let available: &[&[&str]] = &[
+        &["UTF-8"],
+        &["ISO-8859-1", "UTF-8", "KOI8-R"],
+        &["KOI8-R", "utf-8", "UTF-8", "KOI8-R"],
+    ];
+
+    for header in &headers {
+        for available in available {
+            let index = negotiator::charset_index(*header, available);
+            assert_eq!(index.map(|index| available[index].to_string()), negotiator::charset(*header, available));
+
+            let indices = negotiator::charset_indices(*header, available);
+            assert_eq!(
+                indices.iter().map(|&index| available[index].to_string()).collect::<Vec<_>>(),
+                negotiator::charsets(*header, available),
+                "{:?} against {:?}",
+                header,
+                available
+            );
+            assert_eq!(index, indices.first().copied());
+        }
+    }
+
+    assert_eq!(negotiator::charset_indices(Some("KOI8-R, *;q=0.5"), &["UTF-8", "KOI8-R", "KOI8-R"]), vec![1, 0]);
+    assert!(negotiator::charset_indices(None, &[]).is_empty());
+    assert_eq!(negotiator::charset_index(None, &[]), None);
+}
+
+#[test]
+fn negotiator_is_charset_acceptable() {
+    let headers = [
+        None,
+        Some(""),
+        Some("*"),
+        Some("*;q=0"),
+        Some("*, UTF-8;q=0"),
+        Some("UTF-8;q=0, *"),
+        Some("utf-8;q=0, UTF-8;q=0.5"),
+        Some("UTF-8;q=0.5, utf-8;q=0"),
+        Some("ISO-8859-1, *;q=0.1"),
+        Some("ISO-8859-1"),
+        Some("UTF-8;q=0.001"),
+    ];
+
+    for header in &headers {
+        for charset in &["UTF-8", "utf-8", "ISO-8859-1", "KOI8-R"] {
+            assert_eq!(
+                negotiator::is_charset_acceptable(*header, charset),
+                !negotiator::charsets(*header, &[charset]).is_empty(),
+                "{:?} for {:?}",
+                header,
+                charset
+            );
+        }
+    }
+
+    assert!(negotiator::is_charset_acceptable(Some("UTF-8, *;q=0"), "utf-8"));
+    assert!(!negotiator::is_charset_acceptable(Some("*, UTF-8;q=0"), "UTF-8"));
+    assert!(!negotiator::is_charset_acceptable(Some("ISO-8859-1"), "UTF-8"));
+}
+
+#[test]
+fn negotiator_charsets_with_aliases() {
+    use negotiator::charset::canonical_name;
+
+    assert_eq!(canonical_name("utf8"), Some("UTF-8"));
+    assert_eq!(canonical_name("Latin1"), Some("ISO-8859-1"));
+    assert_eq!(canonical_name("iso-8859-1"), Some("ISO-8859-1"));
+    assert_eq!(canonical_name("ascii"), Some("US-ASCII"));
+    assert_eq!(canonical_name("cp1252"), Some("windows-1252"));
+    assert_eq!(canonical_name("x-unknown"), None);
+
+    let provided = ["UTF-8", "ISO-8859-1", "US-ASCII"];
+    assert_eq!(
+        negotiator::charsets_with_aliases(Some("latin1, utf8;q=0.5, ascii;q=0"), &provided),
+        vec!["ISO-8859-1".to_string(), "UTF-8".to_string()]
+    );
+    assert_eq!(
+        negotiator::charsets_with_aliases(Some("UTF-8, ISO-8859-1;q=0.5"), &["latin1", "utf8"]),
+        vec!["utf8".to_string(), "latin1".to_string()]
+    );
+    assert_eq!(
+        negotiator::charsets_with_aliases(Some("*, csASCII;q=0"), &provided),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        negotiator::charsets_with_aliases(Some("x-custom"), &["X-Custom", "UTF-8"]),
+        vec!["X-Custom".to_string()]
+    );
+    assert_eq!(negotiator::charsets_with_aliases(None, &provided), negotiator::charsets(None, &provided));
+
+    // Aliases are opt-in.
+    assert!(negotiator::charsets(Some("utf8, latin1"), &provided).is_empty());
+}
+
+#[test]
+fn negotiator_charsets_weighted() {
+    let available = [("UTF-8", 1.0), ("ISO-8859-1", 0.5), ("KOI8-R", 0.0)];
+
+    // The client weights these equally, so the server's weights decide.
+    assert_eq!(
+        negotiator::charsets_weighted(Some("ISO-8859-1, UTF-8, KOI8-R"), &available),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+    // 0.9 * 0.5 beats 0.4 * 1.0.
+    assert_eq!(
+        negotiator::charsets_weighted(Some("ISO-8859-1;q=0.9, UTF-8;q=0.4"), &available),
+        vec!["ISO-8859-1".to_string(), "UTF-8".to_string()]
+    );
+    // Equal products fall back to the usual order, so the client's q wins.
+    assert_eq!(
+        negotiator::charsets_weighted(Some("UTF-8;q=0.5, ISO-8859-1"), &available),
+        vec!["ISO-8859-1".to_string(), "UTF-8".to_string()]
+    );
+    assert_eq!(
+        negotiator::charsets_weighted(None, &[("ISO-8859-1", 1.0), ("UTF-8", 1.0)]),
+        negotiator::charsets(None, &["ISO-8859-1", "UTF-8"])
+    );
+    assert_eq!(
+        negotiator::charsets_weighted(Some("*;q=1"), &[("UTF-8", f32::NAN), ("KOI8-R", 2.0)]),
+        vec!["KOI8-R".to_string()]
+    );
+    assert!(negotiator::charsets_weighted(Some("UTF-8"), &[]).is_empty());
+}
+
+#[test]
+fn negotiator_charsets_list_whitespace() {
+    let provided = ["ISO-8859-1", "UTF-8"];
+    let clean = negotiator::charsets(Some("UTF-8, ISO-8859-1"), &provided);
+
+    for header in &[
+        "UTF-8,, ISO-8859-1,",
+        "UTF-8,, ISO-8859-1, ",
+        ", ,UTF-8 , ISO-8859-1",
+        "UTF-8\t,\tISO-8859-1\t",
+        "  UTF-8 ;  q=1 ,ISO-8859-1 ; q=1  ",
+    ] {
+        assert_eq!(
+            negotiator::charsets(Some(header), &provided),
+            clean,
+            "{:?}",
+            header
+        );
+        assert_eq!(
+            negotiator::acceptable_charsets(Some(header)),
+            clean,
+            "{:?}",
+            header
+        );
+    }
+    assert!(negotiator::charsets(Some(",, ,"), &provided).is_empty());
+
+    // Only spaces and tabs are optional whitespace.
+    assert_eq!(
+        negotiator::charsets(Some("UTF-8\u{a0}, ISO-8859-1"), &provided),
+        vec!["ISO-8859-1".to_string()]
+    );
+}
+
+#[test]
+fn negotiator_charsets_quality_range() {
+    let provided = ["UTF-8", "ISO-8859-1", "KOI8-R"];
+
+    // q=2 counts as 1, so the header order breaks the tie.
+    assert_eq!(
+        negotiator::charsets(Some("ISO-8859-1, UTF-8;q=2, KOI8-R;q=0.5"), &provided),
+        vec![
+            "ISO-8859-1".to_string(),
+            "UTF-8".to_string(),
+            "KOI8-R".to_string()
+        ]
+    );
+    // q=-0.5 counts as 0, which excludes the charset.
+    assert_eq!(
+        negotiator::charsets(Some("UTF-8;q=-0.5, ISO-8859-1;q=0.5"), &provided),
+        vec!["ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        negotiator::charsets(Some("KOI8-R;q=0.5, UTF-8;q=1.000"), &provided),
+        vec!["UTF-8".to_string(), "KOI8-R".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset_with_weight(Some("UTF-8;q=5"), &provided),
+        Some(("UTF-8".to_string(), 1.0))
+    );
+}
+
+#[test]
+fn negotiator_charset_params() {
+    let parsed = negotiator::charset::parse("UTF-8;foo=bar;q=1, ISO-8859-1;level;q=0.5;x=\"y\"");
+    let params: Vec<Vec<(&str, &str)>> = parsed
+        .iter()
+        .map(|entry| entry.params().collect())
+        .collect();
+    assert_eq!(params, vec![vec![("foo", "bar")], vec![("x", "\"y\"")]]);
+    assert_eq!(parsed[1].clone().into_owned().params().count(), 1);
+
+    assert_eq!(
+        negotiator::charsets(
+            Some("UTF-8;foo=bar;q=1, ISO-8859-1;q=1"),
+            &["ISO-8859-1", "UTF-8"]
+        ),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::AcceptCharsetHeader::parse("UTF-8;foo=bar;q=0.5").to_string(),
+        "UTF-8;foo=bar;q=0.5"
+    );
+}
+
+#[test]
+fn negotiator_charsets_from_parsed() {
+    let header = "UTF-8;q=0.5, ISO-8859-1;foo=bar, KOI8-R;q=0, *;q=0.1";
+    let parsed: Vec<negotiator::AcceptToken<'static>> = negotiator::parse_accept_charset(header)
+        .into_iter()
+        .map(negotiator::AcceptToken::into_owned)
+        .collect();
+
+    let entries: Vec<(&str, f32, usize)> = parsed
+        .iter()
+        .map(|entry| (entry.token(), entry.quality(), entry.index()))
+        .collect();
+    assert_eq!(
+        entries,
+        vec![
+            ("UTF-8", 0.5, 0),
+            ("ISO-8859-1", 1.0, 1),
+            ("KOI8-R", 0.0, 2),
+            ("*", 0.1, 3)
+        ]
+    );
+
+    for provided in &[
+        &["UTF-8", "ISO-8859-1", "KOI8-R", "windows-1252"][..],
+        &["koi8-r"],
+        &[],
+    ] {
+        assert_eq!(
+            negotiator::charsets_from_parsed(&parsed, provided),
+            negotiator::charsets(Some(header), provided),
+            "{:?}",
+            provided
+        );
+    }
+}
+
+#[test]
+fn negotiator_charsets_tie_breaks() {
+    let provided = ["KOI8-R", "UTF-8", "ISO-8859-1"];
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    // Everything matches the same entry, so the provided order decides.
+    assert_eq!(
+        negotiator::charsets(Some("*"), &provided),
+        strings(&["KOI8-R", "UTF-8", "ISO-8859-1"])
+    );
+    assert_eq!(
+        negotiator::charsets(Some("*;q=0.3"), &provided),
+        strings(&["KOI8-R", "UTF-8", "ISO-8859-1"])
+    );
+    // Equal qualities from different entries follow the header order.
+    assert_eq!(
+        negotiator::charsets(
+            Some("ISO-8859-1;q=0.5, UTF-8;q=0.5, KOI8-R;q=0.5"),
+            &provided
+        ),
+        strings(&["ISO-8859-1", "UTF-8", "KOI8-R"])
+    );
+    // An exact match beats `*` at the same quality, wherever it is.
+    assert_eq!(
+        negotiator::charsets(Some("*, UTF-8"), &provided),
+        strings(&["UTF-8", "KOI8-R", "ISO-8859-1"])
+    );
+    assert_eq!(
+        negotiator::charset_indices(Some("*, UTF-8"), &provided),
+        vec![1, 0, 2]
+    );
+
+    let reversed: Vec<&str> = provided.iter().rev().copied().collect();
+    assert_eq!(
+        negotiator::charsets(Some("*"), &reversed),
+        strings(&["ISO-8859-1", "UTF-8", "KOI8-R"])
+    );
+}
+
+#[test]
+fn negotiator_charsets_with_default() {
+    let provided = ["ISO-8859-1", "UTF-8"];
+    let default = "UTF-8, *;q=0.1";
+
+    assert_eq!(
+        negotiator::charsets_with_default(None, &provided, default),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        negotiator::charsets_with_default(None, &provided, "UTF-8"),
+        vec!["UTF-8".to_string()]
+    );
+    // A header the client sent is used as is.
+    assert_eq!(
+        negotiator::charsets_with_default(Some("ISO-8859-1"), &provided, default),
+        vec!["ISO-8859-1".to_string()]
+    );
+    assert!(negotiator::charsets_with_default(Some(""), &provided, default).is_empty());
+    assert_eq!(
+        negotiator::charsets_with_default(None, &provided, "*"),
+        negotiator::charsets(None, &provided)
+    );
+}
+
+#[test]
+fn negotiator_charsets_ref() {
+    let names = [
+        "UTF-8".to_string(),
+        "ISO-8859-1".to_string(),
+        "KOI8-R".to_string(),
+    ];
+    let provided: Vec<&str> = names.iter().map(String::as_str).collect();
+    let header = Some("ISO-8859-1, utf-8;q=0.5, KOI8-R;q=0");
+
+    let preferred = negotiator::charsets_ref(header, &provided);
+    assert_eq!(preferred, vec!["ISO-8859-1", "UTF-8"]);
+    assert!(std::ptr::eq(preferred[0], provided[1]));
+    assert!(std::ptr::eq(preferred[1], provided[0]));
+    assert_eq!(negotiator::charsets(header, &provided), preferred);
+
+    let most_preferred = negotiator::charset_ref(header, &provided).unwrap();
+    assert!(std::ptr::eq(most_preferred, provided[1]));
+    assert_eq!(negotiator::charset_ref(Some("KOI8-R;q=0"), &provided), None);
+    assert!(std::ptr::eq(
+        negotiator::charset_ref(None, &provided).unwrap(),
+        provided[0]
+    ));
+}
+
+#[test]
+fn negotiator_charsets_invalid_quality() {
+    let provided = ["UTF-8", "ISO-8859-1"];
+
+    assert_eq!(
+        negotiator::charsets(Some("UTF-8;q=abc, ISO-8859-1;q=0.5"), &provided),
+        vec!["ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset(Some("UTF-8;q=abc, ISO-8859-1;q=0.5"), &provided),
+        Some("ISO-8859-1".to_string())
+    );
+    // Only the malformed entry is skipped, not others for the same charset.
+    assert_eq!(
+        negotiator::charsets(
+            Some("UTF-8;q=abc, ISO-8859-1;q=0.5, utf-8;q=0.2"),
+            &provided
+        ),
+        vec!["ISO-8859-1".to_string(), "UTF-8".to_string()]
+    );
+    assert_eq!(
+        negotiator::header_stats(
+            "UTF-8;q=abc, ISO-8859-1;q=0.5",
+            negotiator::HeaderKind::AcceptCharset
+        )
+        .malformed,
+        1
+    );
+}
+
+#[test]
+fn negotiator_charsets_quality_precision() {
+    let quality = |header: &str| negotiator::charset::parse(header)[0].quality();
+
+    // Decimals past the third are truncated, not rounded.
+    assert_eq!(quality("UTF-8;q=0.1239"), 0.123);
+    assert_eq!(quality("UTF-8;q=1.0004"), 1.0);
+    assert_eq!(quality("UTF-8;q=0."), 0.0);
+    assert_eq!(
+        negotiator::charsets(
+            Some("UTF-8;q=0.1236, ISO-8859-1;q=0.124"),
+            &["UTF-8", "ISO-8859-1"]
+        ),
+        vec!["ISO-8859-1".to_string(), "UTF-8".to_string()]
+    );
+
+    // Lenient parsing still reads values outside the grammar.
+    assert_eq!(quality("UTF-8;q=.5"), 0.5);
+    assert_eq!(quality("UTF-8;q=-1"), 0.0);
+    assert_eq!(quality("UTF-8;q=99999999999999999999"), 1.0);
+
+    // Other floats are malformed, skipping the entry.
+    for q in &["inf", "infinity", "NaN", "1e-3", "5e-1", "+0.5", "-", "."] {
+        let header = format!("UTF-8;q={}", q);
+        assert!(negotiator::charset::parse(&header).is_empty(), "q={}", q);
+        assert_eq!(
+            negotiator::charsets(
+                Some(&format!("{}, ISO-8859-1;q=0.5", header)),
+                &["UTF-8", "ISO-8859-1"]
+            ),
+            vec!["ISO-8859-1".to_string()],
+            "q={}",
+            q
+        );
+    }
+}
+
+#[test]
+fn negotiator_charsets_with_implicit_latin1() {
+    let provided = ["ISO-8859-1"];
+
+    assert!(negotiator::charsets(Some("UTF-16"), &provided).is_empty());
+    assert_eq!(
+        negotiator::charsets_with_implicit_latin1(Some("UTF-16"), &provided),
+        vec!["ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        negotiator::charsets_with_implicit_latin1(Some("UTF-16;q=0.5"), &["UTF-16", "ISO-8859-1"]),
+        vec!["ISO-8859-1".to_string(), "UTF-16".to_string()]
+    );
+
+    // Mentioning ISO-8859-1 or `*` turns the rule off.
+    for header in &[
+        None,
+        Some("UTF-16, iso-8859-1;q=0"),
+        Some("UTF-16, *;q=0.1"),
+    ] {
+        assert_eq!(
+            negotiator::charsets_with_implicit_latin1(*header, &["ISO-8859-1", "UTF-16"]),
+            negotiator::charsets(*header, &["ISO-8859-1", "UTF-16"]),
+            "{:?}",
+            header
+        );
+    }
+}
+
+#[test]
+fn negotiator_charset_rejections() {
+    let provided = ["UTF-8", "UTF-16"];
+    let header = Some("*, UTF-16;q=0");
+
+    assert_eq!(
+        negotiator::charset_rejections(header, &provided),
+        vec!["UTF-16".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset(header, &provided),
+        Some("UTF-8".to_string())
+    );
+
+    // Not mentioning a charset is not rejecting it.
+    assert!(negotiator::charset_rejections(Some("UTF-8"), &provided).is_empty());
+    assert!(negotiator::charset_rejections(None, &provided).is_empty());
+    assert_eq!(
+        negotiator::charset_rejections(Some("UTF-8, *;q=0"), &provided),
+        vec!["UTF-16".to_string()]
+    );
+    // The most specific entry decides, then the highest quality.
+    assert_eq!(
+        negotiator::charset_rejections(Some("*;q=0, utf-16"), &provided),
+        vec!["UTF-8".to_string()]
+    );
+    assert!(negotiator::charset_rejections(Some("UTF-16;q=0, UTF-16;q=0.5"), &provided).is_empty());
+}
+
+#[test]
+fn negotiator_charsets_with_min_quality() {
+    let provided = ["UTF-8", "ISO-8859-1"];
+
+    assert_eq!(
+        negotiator::charsets_with_min_quality(Some("UTF-8, ISO-8859-1;q=0.01"), &provided, 0.1),
+        vec!["UTF-8".to_string()]
+    );
+    // An entry exactly at the threshold is acceptable.
+    assert_eq!(
+        negotiator::charsets_with_min_quality(Some("UTF-8, ISO-8859-1;q=0.1"), &provided, 0.1),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+    // A wildcard below the threshold doesn't rescue unmatched charsets.
+    assert_eq!(
+        negotiator::charsets_with_min_quality(Some("UTF-8, *;q=0.01"), &provided, 0.1),
+        vec!["UTF-8".to_string()]
+    );
+    assert_eq!(
+        negotiator::charsets_with_min_quality(Some("UTF-8, *;q=0.1"), &provided, 0.1),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+    // Nor does it rescue a specific entry below the threshold.
+    assert!(
+        negotiator::charsets_with_min_quality(Some("UTF-8;q=0.01, *;q=0.5"), &["UTF-8"], 0.1)
+            .is_empty()
+    );
+    // A missing header still accepts anything.
+    assert_eq!(
+        negotiator::charsets_with_min_quality(None, &provided, 0.5),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+
+    let options = negotiator::NegotiationOptions {
+        min_quality: 0.1,
+        ..negotiator::NegotiationOptions::default()
+    };
+    assert_eq!(
+        negotiator::charset::acceptable_with_options(Some("UTF-8, ISO-8859-1;q=0.01"), &options),
+        vec!["UTF-8".to_string()]
+    );
+    let options = negotiator::TokenOptions {
+        wildcard: true,
+        negotiation: options,
+        ..negotiator::TokenOptions::default()
+    };
+    assert!(!negotiator::is_token_acceptable(
+        Some("UTF-8, *;q=0.01"),
+        "ISO-8859-1",
+        &options
+    ));
+}
+
+#[test]
+fn negotiator_explain_charset() {
+    use negotiator::charset::CharsetExplanation;
+
+    let provided = ["UTF-8", "ISO-8859-1", "UTF-16"];
+
+    assert_eq!(
+        negotiator::explain_charset(Some("ISO-8859-1;q=0.5, , utf-8 ; q=0.8"), &provided),
+        Some(CharsetExplanation {
+            charset: "UTF-8".to_string(),
+            entry: "utf-8 ; q=0.8".to_string(),
+            index: 2,
+            exact: true,
+            quality: 0.8,
+        })
+    );
+    assert_eq!(
+        negotiator::explain_charset(Some("UTF-8;q=0.5, *;q=0.9"), &provided),
+        Some(CharsetExplanation {
+            charset: "ISO-8859-1".to_string(),
+            entry: "*;q=0.9".to_string(),
+            index: 1,
+            exact: false,
+            quality: 0.9,
+        })
+    );
+    // A missing header is the same as `*`.
+    assert_eq!(
+        negotiator::explain_charset(None, &provided),
+        Some(CharsetExplanation {
+            charset: "UTF-8".to_string(),
+            entry: "*".to_string(),
+            index: 0,
+            exact: false,
+            quality: 1.0,
+        })
+    );
+    assert_eq!(
+        negotiator::explain_charset(Some("UTF-8;q=0"), &provided),
+        None
+    );
+    assert_eq!(negotiator::explain_charset(None, &[]), None);
+
+    for header in &["UTF-16, *;q=0.2", "iso-8859-1;q=0.3, utf-16;q=0.3", "*;q=0"] {
+        assert_eq!(
+            negotiator::explain_charset(Some(header), &provided)
+                .map(|explanation| explanation.charset),
+            negotiator::charset(Some(header), &provided),
+            "{:?}",
+            header
+        );
+    }
+}
+
+#[test]
+fn negotiator_negotiate_charset() {
+    use negotiator::charset::CharsetOutcome;
+
+    let provided = ["UTF-8", "ISO-8859-1"];
+
+    assert_eq!(
+        negotiator::negotiate_charset(None, &[]),
+        CharsetOutcome::AnyAcceptable
+    );
+    assert_eq!(
+        negotiator::negotiate_charset(Some("*"), &[]),
+        CharsetOutcome::AnyAcceptable
+    );
+    assert_eq!(
+        negotiator::negotiate_charset(Some("UTF-8;q=0, *;q=0.5"), &[]),
+        CharsetOutcome::AnyAcceptable
+    );
+    assert_eq!(
+        negotiator::negotiate_charset(None, &provided),
+        CharsetOutcome::Matched("UTF-8".to_string())
+    );
+    assert_eq!(
+        negotiator::negotiate_charset(Some("ISO-8859-1, UTF-8;q=0.5"), &provided),
+        CharsetOutcome::Matched("ISO-8859-1".to_string())
+    );
+    // Only a literal provided `*` is matched as one.
+    assert_eq!(
+        negotiator::negotiate_charset(Some("*"), &["*"]),
+        CharsetOutcome::Matched("*".to_string())
+    );
+    assert_eq!(
+        negotiator::negotiate_charset(Some("UTF-8;q=0"), &provided),
+        CharsetOutcome::NotAcceptable
+    );
+    assert_eq!(
+        negotiator::negotiate_charset(Some("UTF-8;q=0"), &[]),
+        CharsetOutcome::NotAcceptable
+    );
+    assert_eq!(
+        negotiator::negotiate_charset(Some("KOI8-R"), &[]),
+        CharsetOutcome::NotAcceptable
+    );
+    assert_eq!(
+        negotiator::negotiate_charset(Some("KOI8-R"), &provided),
+        CharsetOutcome::NotAcceptable
+    );
+
+    assert_eq!(CharsetOutcome::AnyAcceptable.into_matched(), None);
+    assert_eq!(negotiator::charset(None, &[]), None);
+}
+
+#[test]
+fn negotiator_charset_server_order() {
+    let provided = ["UTF-8", "ISO-8859-1"];
+    let header = Some("ISO-8859-1;q=1, UTF-8;q=0.5");
+
+    assert_eq!(
+        negotiator::charset_server_order(header, &provided),
+        Some("UTF-8".to_string())
+    );
+    assert_eq!(
+        negotiator::charset(header, &provided),
+        Some("ISO-8859-1".to_string())
+    );
+
+    // Acceptable at all still means q > 0, from the most specific entry.
+    assert_eq!(
+        negotiator::charset_server_order(Some("UTF-8;q=0, *;q=0.1"), &provided),
+        Some("ISO-8859-1".to_string())
+    );
+    assert_eq!(
+        negotiator::charset_server_order(Some("KOI8-R"), &provided),
+        None
+    );
+    assert_eq!(
+        negotiator::charset_server_order(None, &provided),
+        Some("UTF-8".to_string())
+    );
+    assert_eq!(negotiator::charset_server_order(None, &[]), None);
+}
+
+#[test]
+fn negotiator_charsets_with_implicit_latin1_limits() {
+    let provided = ["ISO-8859-1", "UTF-16"];
+
+    // The implied entry doesn't count toward the limits.
+    for count in &[63, 64] {
+        let header: Vec<String> = (0..*count).map(|i| format!("charset-{}", i)).collect();
+        assert_eq!(
+            negotiator::charsets_with_implicit_latin1(Some(&header.join(", ")), &provided[..1]),
+            vec!["ISO-8859-1".to_string()],
+            "{}",
+            count
+        );
+    }
+
+    // Aliases of ISO-8859-1 turn the rule off too.
+    for header in &[
+        "UTF-16, latin1;q=0",
+        "UTF-16, ISO_8859-1;q=0",
+        "UTF-16, l1;q=0.1",
+    ] {
+        assert_eq!(
+            negotiator::charsets_with_implicit_latin1(Some(header), &provided),
+            negotiator::charsets(Some(header), &provided),
+            "{:?}",
+            header
+        );
+    }
+}
+
+#[test]
+fn negotiator_charsets_with_aliases_on_unmatched() {
+    use negotiator::charset;
+    use negotiator::{HeaderKind, NegotiationOptions};
+    use std::sync::{Arc, Mutex};
+
+    let unmatched = Arc::new(Mutex::new(Vec::new()));
+    let seen = unmatched.clone();
+    let options = NegotiationOptions {
+        on_unmatched: Some(Arc::new(move |kind: HeaderKind, token: &str| {
+            seen.lock().unwrap().push((kind, token.to_string()))
+        })),
+        ..NegotiationOptions::default()
+    };
+
+    assert_eq!(
+        charset::preferred_with_aliases_and_options(
+            Some("utf8, latin1;q=0.5"),
+            &["ISO-8859-1"],
+            &options
+        ),
+        vec!["ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        *unmatched.lock().unwrap(),
+        vec![(HeaderKind::AcceptCharset, "utf8".to_string())]
+    );
+}
+
+#[test]
+fn negotiator_charsets_provided_wildcard() {
+    let provided = ["br", "*"];
+
+    // A provided `*` matches a bare wildcard exactly, with or without the fast path.
+    for header in &[None, Some("*"), Some("*;q=1"), Some(" * ")] {
+        assert_eq!(
+            negotiator::charsets(*header, &provided),
+            vec!["*".to_string(), "br".to_string()],
+            "header: {:?}",
+            header
+        );
+        assert_eq!(
+            negotiator::charsets_weighted(*header, &[("br", 1.0), ("*", 1.0)]),
+            negotiator::charsets(*header, &provided),
+            "header: {:?}",
+            header
+        );
+        assert_eq!(
+            negotiator::explain_charset(*header, &provided).map(|explanation| explanation.charset),
+            negotiator::charset(*header, &provided),
+            "header: {:?}",
+            header
+        );
+    }
+}
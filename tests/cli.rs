@@ -0,0 +1,124 @@
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn negotiator(args: &[&str], stdin: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_negotiator"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn cli_text() {
+    let output = negotiator(
+        &[
+            "--accept-charset",
+            "UTF-8;q=0.5, ISO-8859-1",
+            "--charsets",
+            "UTF-8,ISO-8859-1",
+        ],
+        "",
+    );
+    assert_eq!(
+        output,
+        "Accept-Charset: UTF-8;q=0.5, ISO-8859-1
+  selected: ISO-8859-1
+  ISO-8859-1 q=1
+  UTF-8 q=0.5
+Vary: Accept-Charset
+"
+    );
+}
+
+#[test]
+fn cli_stdin_json() {
+    let output = negotiator(
+        &["--stdin", "--json", "--charsets", "UTF-8, KOI8-R"],
+        "Accept-Charset: KOI8-R\naccept-charset: UTF-8;q=0.5\n",
+    );
+    assert_eq!(
+        output,
+        "{\"axes\":{\"Accept-Charset\":{\"value\":\"KOI8-R, UTF-8;q=0.5\",\"selected\":\"KOI8-R\",\
+         \"preferred\":[{\"value\":\"KOI8-R\",\"q\":1},{\"value\":\"UTF-8\",\"q\":0.5}]}},\
+         \"vary\":\"Accept-Charset\"}\n"
+    );
+}
+
+#[test]
+fn cli_missing_header() {
+    let output = negotiator(&["--charsets", "UTF-8", "--json"], "");
+    assert_eq!(
+        output,
+        "{\"axes\":{\"Accept-Charset\":{\"value\":null,\"selected\":\"UTF-8\",\
+         \"preferred\":[{\"value\":\"UTF-8\",\"q\":1}]}},\"vary\":\"Accept-Charset\"}\n"
+    );
+}
+
+#[test]
+fn cli_encodings() {
+    let output = negotiator(
+        &[
+            "--accept-encoding",
+            "gzip, br;q=0.8",
+            "--encodings",
+            "br,gzip",
+            "--te",
+            "trailers, deflate;q=0.5",
+            "--transfer-encodings",
+            "deflate,chunked",
+        ],
+        "",
+    );
+    assert_eq!(
+        output,
+        "Accept-Encoding: gzip, br;q=0.8
+  selected: gzip
+  gzip q=1
+  br q=0.8
+TE: trailers, deflate;q=0.5
+  selected: deflate
+  deflate q=0.5
+  chunked q=0.5
+Vary: Accept-Encoding, TE
+"
+    );
+}
+
+#[test]
+fn cli_all_axes_json() {
+    let output = negotiator(
+        &[
+            "--json",
+            "--stdin",
+            "--charsets",
+            "UTF-8",
+            "--encodings",
+            "gzip",
+            "--transfer-encodings",
+            "gzip",
+        ],
+        "Accept-Encoding: br\n",
+    );
+    assert_eq!(
+        output,
+        "{\"axes\":{\"Accept-Charset\":{\"value\":null,\"selected\":\"UTF-8\",\
+         \"preferred\":[{\"value\":\"UTF-8\",\"q\":1}]},\
+         \"Accept-Encoding\":{\"value\":\"br\",\"selected\":null,\"preferred\":[]},\
+         \"TE\":{\"value\":null,\"selected\":null,\"preferred\":[]}},\
+         \"vary\":\"Accept-Charset, Accept-Encoding, TE\"}\n"
+    );
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "encoding_rs")]
+
+use encoding_rs::{KOI8_R, UTF_8, WINDOWS_1252};
+
+#[test]
+fn negotiator_charset_encoding() {
+    let provided = ["KOI8-R", "ISO-8859-1", "UTF-8"];
+
+    assert_eq!(
+        negotiator::charset_encoding(Some("UTF-8, KOI8-R;q=0.5"), &provided),
+        Some(UTF_8)
+    );
+    assert_eq!(
+        negotiator::charset_encoding(Some("ISO-8859-1"), &provided),
+        Some(WINDOWS_1252)
+    );
+    assert_eq!(negotiator::charset_encoding(None, &provided), Some(KOI8_R));
+    assert_eq!(
+        negotiator::charset_encoding(Some("UTF-16"), &provided),
+        None
+    );
+}
+
+#[test]
+fn negotiator_charset_encoding_unknown_labels() {
+    // A preferred charset encoding_rs doesn't know is passed over.
+    assert_eq!(
+        negotiator::charset_encoding(Some("x-custom, UTF-8;q=0.5"), &["x-custom", "UTF-8"]),
+        Some(UTF_8)
+    );
+    assert_eq!(negotiator::charset_encoding(Some("*"), &["x-custom"]), None);
+}
+
+#[test]
+fn negotiator_charset_encoding_empty_provided() {
+    assert_eq!(negotiator::charset_encoding(None, &[]), Some(UTF_8));
+    assert_eq!(negotiator::charset_encoding(Some("*"), &[]), Some(UTF_8));
+    assert_eq!(
+        negotiator::charset_encoding(Some("x-custom, KOI8-R;q=0.5, *;q=0.1"), &[]),
+        Some(KOI8_R)
+    );
+    assert_eq!(
+        negotiator::charset_encoding(Some("UTF-8;q=0, *"), &[]),
+        None
+    );
+}
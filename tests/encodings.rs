@@ -0,0 +1,849 @@
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn negotiator_encoding() {
+    // Upstream's encoding(), which lists the header.
+    let most_acceptable = |header| negotiator::acceptable_encodings(header).into_iter().next();
+    // Upstream only accepts identity without a header, see `MissingHeader::IdentityOnly`.
+    assert_eq!(most_acceptable(None), Some("*".to_string()));
+    assert_eq!(most_acceptable(Some("*")), Some("*".to_string()));
+    assert_eq!(most_acceptable(Some("*, gzip")), Some("*".to_string()));
+    assert_eq!(most_acceptable(Some("*, gzip;q=0")), Some("*".to_string()));
+    assert_eq!(most_acceptable(Some("*;q=0")), None);
+    assert_eq!(
+        most_acceptable(Some("*;q=0, identity;q=1")),
+        Some("identity".to_string())
+    );
+    assert_eq!(
+        most_acceptable(Some("identity")),
+        Some("identity".to_string())
+    );
+    assert_eq!(most_acceptable(Some("identity;q=0")), None);
+    assert_eq!(most_acceptable(Some("gzip")), Some("gzip".to_string()));
+    assert_eq!(
+        most_acceptable(Some("gzip, compress;q=0")),
+        Some("gzip".to_string())
+    );
+    assert_eq!(
+        most_acceptable(Some("gzip, deflate")),
+        Some("gzip".to_string())
+    );
+    assert_eq!(
+        most_acceptable(Some("gzip;q=0.8, deflate")),
+        Some("deflate".to_string())
+    );
+    assert_eq!(
+        most_acceptable(Some("gzip;q=0.8, identity;q=0.5, *;q=0.3")),
+        Some("gzip".to_string())
+    );
+}
+
+#[test]
+fn negotiator_encoding_array() {
+    let cases: &[(Option<&str>, &[&str], Option<&str>)] = &[
+        (None, &[], None),
+        (None, &["identity"], Some("identity")),
+        // Upstream only accepts identity without a header.
+        (None, &["gzip"], Some("gzip")),
+        (Some("*"), &[], None),
+        (Some("*"), &["identity"], Some("identity")),
+        (Some("*"), &["gzip"], Some("gzip")),
+        (Some("*"), &["gzip", "identity"], Some("gzip")),
+        (Some("*, gzip"), &["identity"], Some("identity")),
+        (Some("*, gzip"), &["gzip"], Some("gzip")),
+        (Some("*, gzip"), &["compress", "gzip"], Some("gzip")),
+        (Some("*, gzip;q=0"), &["gzip"], None),
+        (Some("*, gzip;q=0"), &["gzip", "identity"], Some("identity")),
+        (Some("*;q=0"), &["identity"], None),
+        (Some("*;q=0"), &["gzip"], None),
+        (Some("*;q=0, identity;q=1"), &["gzip"], None),
+        (Some("*;q=0, identity;q=1"), &["identity"], Some("identity")),
+        (Some("identity"), &[], None),
+        (Some("identity"), &["identity"], Some("identity")),
+        (Some("identity"), &["gzip"], None),
+        (Some("identity;q=0"), &["identity"], None),
+        (Some("identity;q=0"), &["gzip"], None),
+        (Some("gzip"), &[], None),
+        (Some("gzip"), &["gzip"], Some("gzip")),
+        (Some("gzip"), &["identity", "gzip"], Some("gzip")),
+        (Some("gzip"), &["identity"], Some("identity")),
+        (Some("gzip, compress;q=0"), &["compress"], None),
+        (Some("gzip, compress;q=0"), &["deflate", "compress"], None),
+        (
+            Some("gzip, compress;q=0"),
+            &["gzip", "compress"],
+            Some("gzip"),
+        ),
+        // Upstream breaks the tie by the header's order, for gzip.
+        (Some("gzip, deflate"), &["deflate", "gzip"], Some("deflate")),
+        (Some("gzip;q=0.8, deflate"), &["gzip"], Some("gzip")),
+        (Some("gzip;q=0.8, deflate"), &["deflate"], Some("deflate")),
+        (
+            Some("gzip;q=0.8, deflate"),
+            &["deflate", "gzip"],
+            Some("deflate"),
+        ),
+        (
+            Some("gzip;q=0.8, identity;q=0.5, *;q=0.3"),
+            &["gzip"],
+            Some("gzip"),
+        ),
+        (
+            Some("gzip;q=0.8, identity;q=0.5, *;q=0.3"),
+            &["compress", "identity"],
+            Some("identity"),
+        ),
+    ];
+
+    for &(header, provided, expected) in cases {
+        assert_eq!(
+            negotiator::encoding(header, provided),
+            expected.map(str::to_string),
+            "header: {:?}, provided: {:?}",
+            header,
+            provided
+        );
+    }
+}
+
+#[test]
+fn negotiator_encodings() {
+    let cases: &[(Option<&str>, &[&str])] = &[
+        // Upstream only accepts identity without a header.
+        (None, &["*"]),
+        (Some("*"), &["*"]),
+        (Some("*, gzip"), &["*", "gzip"]),
+        (Some("*, gzip;q=0"), &["*"]),
+        (Some("*;q=0"), &[]),
+        (Some("*;q=0, identity;q=1"), &["identity"]),
+        (Some("identity"), &["identity"]),
+        (Some("identity;q=0"), &[]),
+        (Some("gzip"), &["gzip", "identity"]),
+        (Some("gzip, compress;q=0"), &["gzip", "identity"]),
+        (Some("gzip, deflate"), &["gzip", "deflate", "identity"]),
+        (
+            Some("gzip;q=0.8, deflate"),
+            &["deflate", "gzip", "identity"],
+        ),
+        (
+            Some("gzip;foo=bar;q=1, deflate;q=1"),
+            &["gzip", "deflate", "identity"],
+        ),
+        (
+            Some("gzip;q=0.8, identity;q=0.5, *;q=0.3"),
+            &["gzip", "identity", "*"],
+        ),
+    ];
+
+    for &(header, expected) in cases {
+        assert_eq!(
+            negotiator::acceptable_encodings(header),
+            strings(expected),
+            "header: {:?}",
+            header
+        );
+    }
+}
+
+#[test]
+fn negotiator_encodings_array() {
+    let cases: &[(Option<&str>, &[&str], &[&str])] = &[
+        (None, &[], &[]),
+        (None, &["identity"], &["identity"]),
+        // Upstream only accepts identity without a header.
+        (None, &["gzip"], &["gzip"]),
+        (None, &["gzip", "identity"], &["gzip", "identity"]),
+        (Some("*"), &[], &[]),
+        (Some("*"), &["identity"], &["identity"]),
+        (Some("*"), &["gzip"], &["gzip"]),
+        (Some("*"), &["gzip", "identity"], &["gzip", "identity"]),
+        (
+            Some("*, gzip"),
+            &["gzip", "identity"],
+            &["gzip", "identity"],
+        ),
+        (
+            Some("*, gzip"),
+            &["identity", "gzip"],
+            &["gzip", "identity"],
+        ),
+        (Some("*, gzip;q=0"), &["gzip", "identity"], &["identity"]),
+        (Some("*;q=0"), &["gzip", "identity"], &[]),
+        (
+            Some("*;q=0, identity;q=1"),
+            &["gzip", "identity"],
+            &["identity"],
+        ),
+        (Some("identity"), &["gzip", "identity"], &["identity"]),
+        (Some("identity;q=0"), &["gzip", "identity"], &[]),
+        (Some("gzip"), &["gzip", "identity"], &["gzip", "identity"]),
+        (Some("gzip"), &["identity", "gzip"], &["gzip", "identity"]),
+        (Some("gzip, compress;q=0"), &["gzip", "compress"], &["gzip"]),
+        // Upstream breaks the tie by the header's order, for gzip first.
+        (
+            Some("gzip, deflate"),
+            &["deflate", "gzip"],
+            &["deflate", "gzip"],
+        ),
+        (
+            Some("gzip;q=0.8, deflate"),
+            &["gzip", "deflate"],
+            &["deflate", "gzip"],
+        ),
+        (
+            Some("gzip;q=0.8, identity;q=0.5, *;q=0.3"),
+            &["identity", "gzip", "compress"],
+            &["gzip", "identity", "compress"],
+        ),
+    ];
+
+    for &(header, provided, expected) in cases {
+        assert_eq!(
+            negotiator::encodings(header, provided),
+            strings(expected),
+            "header: {:?}, provided: {:?}",
+            header,
+            provided
+        );
+    }
+}
+
+#[test]
+fn negotiator_encoding_implied_identity() {
+    assert_eq!(
+        negotiator::encoding(Some("gzip"), &["identity", "gzip"]),
+        Some("gzip".to_string())
+    );
+    assert_eq!(
+        negotiator::encoding(Some("br"), &["identity"]),
+        Some("identity".to_string())
+    );
+    assert_eq!(
+        negotiator::encodings(Some("gzip, identity;q=0.1"), &["identity", "gzip"]),
+        strings(&["gzip", "identity"])
+    );
+    // The implied identity has the lowest quality in the header, after every coding named.
+    assert_eq!(
+        negotiator::encodings(Some("br;q=0.5, gzip;q=0.8"), &["identity", "br", "deflate"]),
+        strings(&["br", "identity"])
+    );
+    assert_eq!(
+        negotiator::acceptable_encodings(Some("br;q=0.5, gzip;q=0.8, deflate;q=0")),
+        strings(&["gzip", "br", "identity"])
+    );
+    // A wildcard mentions identity, at its own quality.
+    assert_eq!(
+        negotiator::encodings(Some("gzip;q=0.5, *;q=0.1"), &["identity", "gzip"]),
+        strings(&["gzip", "identity"])
+    );
+    assert_eq!(
+        negotiator::acceptable_encodings(Some("gzip;q=0.5, *;q=0.1")),
+        strings(&["gzip", "*"])
+    );
+    // Names are compared case-insensitively, as for any coding.
+    assert_eq!(
+        negotiator::encoding(Some("IDENTITY;q=0, br"), &["identity"]),
+        None
+    );
+}
+
+#[test]
+fn negotiator_encoding_excluded_identity() {
+    let provided = ["identity", "gzip"];
+
+    assert_eq!(
+        negotiator::encoding(Some("identity;q=0"), &["identity"]),
+        None
+    );
+    assert_eq!(negotiator::encoding(Some("*;q=0"), &provided), None);
+    assert!(negotiator::encodings(Some("*;q=0"), &provided).is_empty());
+    assert_eq!(
+        negotiator::encoding(Some("*;q=0, gzip"), &provided),
+        Some("gzip".to_string())
+    );
+    assert_eq!(
+        negotiator::encodings(Some("*;q=0, gzip"), &provided),
+        strings(&["gzip"])
+    );
+    // An exclusion by name wins over a wildcard, in either order.
+    assert_eq!(
+        negotiator::encoding(Some("identity;q=0, *"), &["identity"]),
+        None
+    );
+    assert_eq!(
+        negotiator::encoding(Some("*, identity;q=0"), &["identity"]),
+        None
+    );
+    assert_eq!(
+        negotiator::encodings(Some("identity;q=0, *"), &provided),
+        strings(&["gzip"])
+    );
+    // A named identity wins over an excluding wildcard.
+    assert_eq!(
+        negotiator::encoding(Some("*;q=0, identity;q=0.5"), &provided),
+        Some("identity".to_string())
+    );
+    // Excluding other codings leaves identity implied.
+    assert_eq!(
+        negotiator::encoding(Some("gzip;q=0"), &provided),
+        Some("identity".to_string())
+    );
+}
+
+#[test]
+fn negotiator_encodings_wildcard_precedence() {
+    let header = Some("gzip;q=1.0, *;q=0.5");
+
+    assert_eq!(
+        negotiator::encodings(header, &["br", "gzip"]),
+        strings(&["gzip", "br"])
+    );
+    assert_eq!(
+        negotiator::encodings(header, &["br", "identity", "gzip"]),
+        strings(&["gzip", "br", "identity"])
+    );
+    assert_eq!(
+        negotiator::encodings(Some("*, gzip;q=0"), &["gzip", "br"]),
+        strings(&["br"])
+    );
+    // An exact entry overrides the wildcard even when it has a lower quality.
+    assert_eq!(
+        negotiator::encodings(Some("*, gzip;q=0.2"), &["gzip", "br"]),
+        strings(&["br", "gzip"])
+    );
+    assert_eq!(
+        negotiator::encodings(Some("gzip;q=0.2, *"), &["gzip", "br"]),
+        strings(&["br", "gzip"])
+    );
+}
+
+#[test]
+fn negotiator_encoding_aliases() {
+    assert_eq!(
+        negotiator::encoding(Some("x-gzip"), &["gzip"]),
+        Some("gzip".to_string())
+    );
+    assert_eq!(
+        negotiator::encoding(Some("gzip"), &["x-gzip"]),
+        Some("x-gzip".to_string())
+    );
+    assert_eq!(
+        negotiator::encodings(
+            Some("X-Compress;q=0.5, gzip"),
+            &["compress", "x-gzip", "br"]
+        ),
+        strings(&["x-gzip", "compress"])
+    );
+    // An exclusion by either name excludes the coding.
+    assert_eq!(
+        negotiator::encoding(Some("*, x-gzip;q=0"), &["gzip", "br"]),
+        Some("br".to_string())
+    );
+
+    assert_eq!(negotiator::encoding::canonical_name("X-GZIP"), Some("gzip"));
+    assert_eq!(
+        negotiator::encoding::canonical_name("compress"),
+        Some("compress")
+    );
+    assert_eq!(negotiator::encoding::canonical_name("br"), None);
+}
+
+#[test]
+fn negotiator_encodings_provided_order() {
+    let header = Some("gzip, deflate, br");
+
+    for provided in &[
+        ["br", "gzip", "deflate"],
+        ["gzip", "deflate", "br"],
+        ["deflate", "br", "gzip"],
+    ] {
+        assert_eq!(negotiator::encodings(header, provided), strings(provided));
+    }
+    // Ties only come after quality and an exact match.
+    assert_eq!(
+        negotiator::encodings(Some("gzip;q=0.9, deflate, br"), &["gzip", "br", "deflate"]),
+        strings(&["br", "deflate", "gzip"])
+    );
+    assert_eq!(
+        negotiator::encodings(Some("*, br"), &["gzip", "br"]),
+        strings(&["br", "gzip"])
+    );
+    assert_eq!(
+        negotiator::encodings(header, &["identity", "br", "gzip"]),
+        strings(&["br", "gzip", "identity"])
+    );
+}
+
+#[test]
+fn negotiator_encodings_with_weights() {
+    let cases: &[(Option<&str>, &[&str])] = &[
+        (None, &["gzip", "identity"]),
+        (Some("gzip;q=0.8, *;q=0.5"), &["br", "gzip", "identity"]),
+        (Some("gzip;q=0.8, br;q=0.4"), &["identity", "br", "gzip"]),
+        (Some("gzip, deflate, br"), &["br", "gzip", "deflate"]),
+        (Some("identity;q=0"), &["identity"]),
+    ];
+    for &(header, provided) in cases {
+        let weighted = negotiator::encodings_with_weights(header, provided);
+        let names: Vec<String> = weighted.iter().map(|(coding, _)| coding.clone()).collect();
+        assert_eq!(
+            names,
+            negotiator::encodings(header, provided),
+            "{:?}",
+            header
+        );
+        assert_eq!(
+            negotiator::encoding_with_weight(header, provided),
+            weighted.into_iter().next(),
+            "{:?}",
+            header
+        );
+    }
+
+    // The wildcard's quality, and the implied identity's, when those matched.
+    assert_eq!(
+        negotiator::encodings_with_weights(Some("gzip;q=0.8, *;q=0.5"), &["br", "gzip"]),
+        vec![("gzip".to_string(), 0.8), ("br".to_string(), 0.5)]
+    );
+    assert_eq!(
+        negotiator::encodings_with_weights(Some("gzip;q=0.8, br;q=0.4"), &["identity", "gzip"]),
+        vec![("gzip".to_string(), 0.8), ("identity".to_string(), 0.4)]
+    );
+    assert_eq!(
+        negotiator::encoding_with_weight(Some("br;q=0.4"), &["gzip", "identity"]),
+        Some(("identity".to_string(), 0.4))
+    );
+    assert_eq!(
+        negotiator::encoding_with_weight(Some("*;q=0"), &["gzip"]),
+        None
+    );
+}
+
+#[test]
+fn negotiator_negotiate_encoding() {
+    use negotiator::encoding::{EncodingMatch, MatchSource};
+
+    let matched = |coding: &str, quality, source| {
+        Some(EncodingMatch {
+            coding: coding.to_string(),
+            quality,
+            source,
+        })
+    };
+    let provided = ["br", "gzip", "identity"];
+
+    assert_eq!(
+        negotiator::negotiate_encoding(Some("gzip"), &provided),
+        matched("gzip", 1.0, MatchSource::Exact)
+    );
+    assert_eq!(
+        negotiator::negotiate_encoding(Some("X-GZIP;q=0.5"), &provided),
+        matched("gzip", 0.5, MatchSource::Exact)
+    );
+    assert_eq!(
+        negotiator::negotiate_encoding(Some("*"), &provided),
+        matched("br", 1.0, MatchSource::Wildcard)
+    );
+    assert_eq!(
+        negotiator::negotiate_encoding(Some("*;q=0.5, gzip;q=0"), &["gzip", "identity"]),
+        matched("identity", 0.5, MatchSource::Wildcard)
+    );
+    assert_eq!(
+        negotiator::negotiate_encoding(None, &provided),
+        matched("br", 1.0, MatchSource::Wildcard)
+    );
+    assert_eq!(
+        negotiator::negotiate_encoding(Some("deflate;q=0.3"), &provided),
+        matched("identity", 0.3, MatchSource::ImplicitIdentity)
+    );
+    assert_eq!(
+        negotiator::negotiate_encoding(Some("identity"), &provided),
+        matched("identity", 1.0, MatchSource::Exact)
+    );
+    assert_eq!(
+        negotiator::negotiate_encoding(Some("*;q=0"), &provided),
+        None
+    );
+    assert_eq!(
+        negotiator::negotiate_encoding(Some("br;q=0.5, gzip"), &provided).map(|m| m.coding),
+        negotiator::encoding(Some("br;q=0.5, gzip"), &provided)
+    );
+}
+
+#[test]
+fn negotiator_encodings_missing_header() {
+    use negotiator::encoding::{EncodingOptions, MissingHeader};
+
+    let provided = ["gzip", "identity"];
+    let identity_only = EncodingOptions {
+        missing_header: MissingHeader::IdentityOnly,
+        ..EncodingOptions::default()
+    };
+
+    assert_eq!(
+        negotiator::encoding::preferred_with_options(None, &provided, &EncodingOptions::default()),
+        strings(&["gzip", "identity"])
+    );
+    assert_eq!(
+        negotiator::encodings(None, &provided),
+        strings(&["gzip", "identity"])
+    );
+    assert_eq!(
+        negotiator::encoding::preferred_with_options(None, &provided, &identity_only),
+        strings(&["identity"])
+    );
+    assert!(
+        negotiator::encoding::preferred_with_options(None, &["gzip"], &identity_only).is_empty()
+    );
+    // A header the client sent is used as is.
+    assert_eq!(
+        negotiator::encoding::preferred_with_options(Some("gzip"), &provided, &identity_only),
+        strings(&["gzip", "identity"])
+    );
+}
+
+#[test]
+fn negotiator_is_encoding_acceptable() {
+    assert!(negotiator::is_encoding_acceptable(None, "identity"));
+    assert!(negotiator::is_encoding_acceptable(None, "gzip"));
+    assert!(negotiator::is_encoding_acceptable(Some("gzip"), "identity"));
+    assert!(negotiator::is_encoding_acceptable(
+        Some("gzip;q=0.5, br;q=0"),
+        "identity"
+    ));
+    assert!(negotiator::is_encoding_acceptable(
+        Some("identity;q=0.1"),
+        "identity"
+    ));
+    assert!(!negotiator::is_encoding_acceptable(
+        Some("identity;q=0"),
+        "identity"
+    ));
+    assert!(!negotiator::is_encoding_acceptable(
+        Some("*;q=0"),
+        "identity"
+    ));
+    assert!(!negotiator::is_encoding_acceptable(
+        Some("gzip, *;q=0"),
+        "identity"
+    ));
+    assert!(negotiator::is_encoding_acceptable(
+        Some("*;q=0, identity"),
+        "identity"
+    ));
+    assert!(!negotiator::is_encoding_acceptable(Some("gzip"), "br"));
+    assert!(negotiator::is_encoding_acceptable(Some("x-gzip"), "gzip"));
+
+    let headers = [
+        "",
+        "*",
+        "gzip",
+        "gzip;q=0",
+        "gzip;q=0, gzip",
+        "*, gzip;q=0",
+        "*;q=0, x-gzip;q=0.5",
+        "br;q=0.5, deflate",
+        "identity;q=0, *",
+        "*;q=0.1, identity;q=0",
+        ";q=1, gzip;;q=abc",
+    ];
+    for header in &headers {
+        for coding in &["gzip", "x-gzip", "br", "identity", "IDENTITY"] {
+            assert_eq!(
+                negotiator::is_encoding_acceptable(Some(header), coding),
+                !negotiator::encodings(Some(header), &[coding]).is_empty(),
+                "header: {:?}, coding: {:?}",
+                header,
+                coding
+            );
+        }
+    }
+}
+
+#[test]
+fn negotiator_encodings_weighted() {
+    let available = [("br", 1.0), ("zstd", 0.9), ("gzip", 0.5)];
+    assert_eq!(
+        negotiator::encodings_weighted(Some("gzip, deflate, br, zstd"), &available),
+        vec!["br", "zstd", "gzip"]
+    );
+    assert_eq!(
+        negotiator::encodings_weighted(Some("gzip, br;q=0.4"), &available),
+        vec!["gzip", "br"]
+    );
+
+    // Ties after weighing keep the provided order.
+    assert_eq!(
+        negotiator::encodings_weighted(Some("br;q=0.5, gzip"), &[("br", 1.0), ("gzip", 0.5)]),
+        vec!["br", "gzip"]
+    );
+    assert_eq!(
+        negotiator::encodings_weighted(Some("br;q=0.5, gzip"), &[("gzip", 0.5), ("br", 1.0)]),
+        vec!["gzip", "br"]
+    );
+
+    // A weight of 0 removes the coding.
+    assert_eq!(
+        negotiator::encodings_weighted(Some("gzip, br"), &[("br", 0.0), ("gzip", 0.5)]),
+        vec!["gzip"]
+    );
+    assert_eq!(
+        negotiator::encodings_weighted(Some("*"), &[("br", f32::NAN), ("gzip", 2.0)]),
+        vec!["gzip"]
+    );
+
+    // Aliases and the implied identity negotiate as without weights.
+    assert_eq!(
+        negotiator::encodings_weighted(
+            Some("x-gzip, br"),
+            &[("identity", 1.0), ("br", 1.0), ("gzip", 1.0)]
+        ),
+        vec!["br", "gzip", "identity"]
+    );
+    assert_eq!(
+        negotiator::encodings_weighted(None, &[("identity", 0.1), ("gzip", 0.9)]),
+        vec!["gzip", "identity"]
+    );
+    assert!(negotiator::encodings_weighted(Some("gzip"), &[]).is_empty());
+}
+
+#[test]
+fn negotiator_encoding_chain() {
+    let chains: &[&[&str]] = &[&["br"], &["gzip"], &["identity"]];
+    assert_eq!(
+        negotiator::encoding_chain(Some("gzip, br;q=0.8"), chains),
+        Some(&["gzip"][..])
+    );
+    assert_eq!(
+        negotiator::encoding_chain(Some("gzip, br"), chains),
+        Some(&["br"][..])
+    );
+    assert_eq!(
+        negotiator::encoding_chain(Some("deflate"), chains),
+        Some(&["identity"][..])
+    );
+    assert_eq!(
+        negotiator::encoding_chain(Some("identity;q=0"), chains),
+        None
+    );
+
+    // Every coding in the chain must be acceptable.
+    let chains: &[&[&str]] = &[&["gzip", "aes128gcm"], &["gzip"]];
+    assert_eq!(
+        negotiator::encoding_chain(Some("gzip, aes128gcm;q=0"), chains),
+        Some(&["gzip"][..])
+    );
+    assert_eq!(
+        negotiator::encoding_chain(Some("gzip, *;q=0"), chains),
+        Some(&["gzip"][..])
+    );
+    assert_eq!(
+        negotiator::encoding_chain(Some("x-gzip, aes128gcm"), chains),
+        Some(&["gzip", "aes128gcm"][..])
+    );
+
+    // A chain is as preferred as its least preferred coding.
+    assert_eq!(
+        negotiator::encoding_chain(Some("gzip;q=0.6, aes128gcm;q=0.5"), chains),
+        Some(&["gzip"][..])
+    );
+    assert_eq!(
+        negotiator::encoding_chain(Some("gzip;q=0.5, aes128gcm"), chains),
+        Some(&["gzip", "aes128gcm"][..])
+    );
+
+    // An empty chain is identity.
+    let chains: &[&[&str]] = &[&["gzip"], &[]];
+    assert_eq!(
+        negotiator::encoding_chain(Some("gzip;q=0"), chains),
+        Some(&[][..])
+    );
+    assert_eq!(negotiator::encoding_chain(Some("*;q=0"), chains), None);
+    assert_eq!(negotiator::encoding_chain(None, &[]), None);
+}
+
+#[test]
+fn negotiator_encodings_parse() {
+    let headers = [
+        "gzip, deflate, br",
+        "gzip, deflate, br, zstd",
+        "gzip",
+        "identity",
+        "*",
+        "x-gzip, X-Compress",
+        // These take the general parser.
+        "",
+        "gzip,deflate",
+        "gzip, deflate;q=0.5",
+        "gzip,  br",
+        " gzip, br ",
+        "gzip, , br",
+        "gzip, br,",
+        "gz ip, br",
+        "gzip, b\u{e9}",
+    ];
+    for header in &headers {
+        assert_eq!(
+            format!("{:?}", negotiator::encoding::parse(header)),
+            format!("{:?}", negotiator::parse_tokens(header)),
+            "{:?}",
+            header
+        );
+    }
+}
+
+#[test]
+fn negotiator_encodings_min_quality() {
+    use negotiator::encoding::EncodingOptions;
+    use negotiator::NegotiationOptions;
+
+    let options = EncodingOptions {
+        negotiation: NegotiationOptions {
+            min_quality: 0.5,
+            ..NegotiationOptions::default()
+        },
+        ..EncodingOptions::default()
+    };
+    let preferred = |accept: &str, provided: &[&str]| {
+        negotiator::encoding::preferred_with_options(Some(accept), provided, &options)
+    };
+    let provided = ["br", "gzip", "identity"];
+
+    // The threshold itself is acceptable.
+    assert_eq!(
+        preferred("br;q=0.5, gzip;q=0.499", &provided),
+        strings(&["br"])
+    );
+    assert_eq!(preferred("*;q=0.5", &provided), strings(&provided));
+    assert_eq!(preferred("*;q=0.499, br", &provided), strings(&["br"]));
+
+    // identity is left when nothing clears the bar, unless it is excluded.
+    assert_eq!(preferred("br;q=0.001", &provided), strings(&["identity"]));
+    assert_eq!(
+        preferred("br;q=0.1, identity;q=0.2", &provided),
+        strings(&["identity"])
+    );
+    assert_eq!(preferred("*;q=0.1", &provided), strings(&["identity"]));
+    assert!(preferred("br;q=0.1, identity;q=0", &provided).is_empty());
+    assert!(preferred("br;q=0.1, *;q=0", &provided).is_empty());
+    assert!(preferred("br;q=0.001", &["br", "gzip"]).is_empty());
+
+    // With a coding above the bar, identity must clear it too.
+    assert_eq!(
+        preferred("gzip, br;q=0.1, identity;q=0.2", &provided),
+        strings(&["gzip"])
+    );
+}
+
+#[test]
+fn negotiator_precompressed_extension() {
+    use negotiator::encoding::{Precompressed, PrecompressedKind::*};
+
+    let all = [Brotli, Zstd, Gzip, Identity];
+    assert_eq!(
+        negotiator::precompressed_extension(Some("br;q=0, gzip"), &all),
+        Some(".gz")
+    );
+    assert_eq!(
+        negotiator::precompressed_extension(Some("gzip, deflate, br, zstd"), &all),
+        Some(".br")
+    );
+    assert_eq!(
+        negotiator::precompressed_extension(Some("gzip;q=0.5, zstd"), &all),
+        Some(".zst")
+    );
+    assert_eq!(
+        negotiator::precompressed_extension(Some("deflate"), &all),
+        Some("")
+    );
+    assert_eq!(
+        negotiator::precompressed_extension(Some("x-gzip"), &[Brotli, Gzip]),
+        Some(".gz")
+    );
+    assert_eq!(
+        negotiator::precompressed_extension(Some("*;q=0"), &all),
+        None
+    );
+    assert_eq!(negotiator::precompressed_extension(Some("br"), &[]), None);
+
+    let files = Precompressed::new(vec![Gzip, Identity]);
+    assert_eq!(files.extension(None), Some(".gz"));
+    assert_eq!(files.extension(Some("br;q=0, gzip;q=0")), Some(""));
+    assert_eq!(files.extension(Some("identity;q=0, br")), None);
+}
+
+#[test]
+fn negotiator_rewrite_accept_encoding() {
+    let supported = ["gzip", "br", "zstd"];
+    let rewrite = |header| negotiator::rewrite_accept_encoding(header, &supported, true);
+
+    assert_eq!(rewrite(Some("gzip, deflate, br;q=0.5")), "gzip, br;q=0.5");
+    assert_eq!(
+        rewrite(Some("x-gzip;q=0.8, *;q=0.1")),
+        "gzip;q=0.8, br;q=0.1, zstd;q=0.1"
+    );
+    assert_eq!(rewrite(None), "gzip, br, zstd");
+
+    // Exclusions survive the rewrite.
+    assert_eq!(rewrite(Some("br;q=0, *")), "gzip, br;q=0, zstd");
+    assert_eq!(rewrite(Some("gzip, identity;q=0")), "gzip, identity;q=0");
+    assert_eq!(
+        rewrite(Some("*;q=0, br")),
+        "gzip;q=0, br, zstd;q=0, identity;q=0"
+    );
+
+    // With nothing in common, upstream is asked for identity.
+    assert_eq!(rewrite(Some("deflate")), "identity");
+    assert_eq!(rewrite(Some("")), "identity");
+    assert_eq!(rewrite(Some("gzip;q=0, deflate")), "identity, gzip;q=0");
+    assert_eq!(
+        negotiator::rewrite_accept_encoding(Some("br"), &[], true),
+        "identity"
+    );
+    // Unless the client refuses that too.
+    assert_eq!(rewrite(Some("deflate, identity;q=0")), "identity;q=0");
+
+    // Without passthrough the proxy decodes, so it asks for what it supports.
+    assert_eq!(
+        negotiator::rewrite_accept_encoding(Some("identity"), &supported, false),
+        "gzip, br, zstd"
+    );
+    assert_eq!(
+        negotiator::rewrite_accept_encoding(Some("br"), &[], false),
+        "identity"
+    );
+}
+
+#[test]
+fn negotiator_encodings_on_unmatched() {
+    use negotiator::encoding::{self, EncodingOptions};
+    use negotiator::{HeaderKind, NegotiationOptions};
+    use std::sync::{Arc, Mutex};
+
+    let unmatched = Arc::new(Mutex::new(Vec::new()));
+    let seen = unmatched.clone();
+    let options = EncodingOptions {
+        negotiation: NegotiationOptions {
+            on_unmatched: Some(Arc::new(move |kind: HeaderKind, token: &str| {
+                seen.lock().unwrap().push((kind, token.to_string()))
+            })),
+            ..NegotiationOptions::default()
+        },
+        ..EncodingOptions::default()
+    };
+
+    // The implied identity isn't reported, and aliases are spelled as sent.
+    assert_eq!(
+        encoding::preferred_with_options(Some("x-gzip, br"), &["deflate"], &options),
+        strings(&[])
+    );
+    assert_eq!(
+        *unmatched.lock().unwrap(),
+        vec![
+            (HeaderKind::AcceptEncoding, "x-gzip".to_string()),
+            (HeaderKind::AcceptEncoding, "br".to_string()),
+        ]
+    );
+}
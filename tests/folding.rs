@@ -0,0 +1,150 @@
+use negotiator::{
+    header_stats, preferred_tokens, try_preferred_tokens_with_options, HeaderKind,
+    NegotiationError, NegotiationOptions, TokenOptions,
+};
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn negotiator_folded_accept_language() {
+    let header = "da,\r\n en-GB;q=0.8,\r\n\t en;q=0.7";
+    let provided = ["en", "da", "en-GB"];
+
+    assert_eq!(
+        preferred_tokens(Some(header), &provided),
+        preferred_tokens(Some("da, en-GB;q=0.8, en;q=0.7"), &provided)
+    );
+    // A fold inside the parameters still leaves a readable q.
+    assert_eq!(
+        preferred_tokens(Some("da;q=0.5;\r\n q=0.2, en;q=0.3"), &provided),
+        strings(&["en", "da"])
+    );
+    assert_eq!(
+        try_preferred_tokens_with_options(Some(header), &provided, &TokenOptions::default()),
+        Ok(strings(&["da", "en-GB", "en"]))
+    );
+}
+
+#[test]
+fn negotiator_folded_accept() {
+    let header = "text/html,\r\n application/xhtml+xml;q=0.9,\r\n */*;q=0.8";
+    let provided = ["application/xhtml+xml", "text/html"];
+
+    assert_eq!(
+        preferred_tokens(Some(header), &provided),
+        strings(&["text/html", "application/xhtml+xml"])
+    );
+}
+
+#[test]
+fn negotiator_control_characters() {
+    let provided = ["UTF-8", "ISO-8859-1"];
+
+    // A bare CR or LF is not a fold, and invalidates its entry.
+    for header in &[
+        "UTF-8\r, ISO-8859-1;q=0.5",
+        "UTF-8\n, ISO-8859-1;q=0.5",
+        "UTF-\r\n8, ISO-8859-1;q=0.5",
+        "U\x00TF-8, ISO-8859-1;q=0.5",
+        "UTF-8;q=\x7f1, ISO-8859-1;q=0.5",
+    ] {
+        assert_eq!(
+            negotiator::charset::preferred(Some(header), &provided),
+            strings(&["ISO-8859-1"]),
+            "{:?}",
+            header
+        );
+    }
+    assert_eq!(
+        negotiator::charset::try_preferred_with_options(
+            Some("ISO-8859-1, UTF-8\n"),
+            &provided,
+            &NegotiationOptions::default()
+        ),
+        Err(NegotiationError::ControlCharacter(17))
+    );
+
+    let stats = header_stats("UTF-8\x01, \r\n ISO-8859-1", HeaderKind::AcceptCharset);
+    assert_eq!((stats.entries, stats.malformed), (1, 1));
+}
+
+#[test]
+fn negotiator_raw_tab() {
+    let provided = ["UTF-8", "ISO-8859-1"];
+
+    assert_eq!(
+        negotiator::charset::preferred(Some("ISO-8859-1;\tq=0.5,\tUTF-8\t"), &provided),
+        strings(&["UTF-8", "ISO-8859-1"])
+    );
+    // Whitespace can't appear inside a token.
+    assert_eq!(
+        negotiator::charset::preferred(Some("UTF\t-8, ISO-8859-1"), &provided),
+        strings(&["ISO-8859-1"])
+    );
+}
+
+#[test]
+fn negotiator_control_characters_wildcard() {
+    let provided = ["UTF-8"];
+
+    // A bare `*` with a control character is no wildcard either.
+    for header in &["\n*", "*\r\n"] {
+        assert!(
+            negotiator::charsets(Some(header), &provided).is_empty(),
+            "{:?}",
+            header
+        );
+        assert!(
+            !negotiator::is_encoding_acceptable(Some(header), "gzip"),
+            "{:?}",
+            header
+        );
+        assert!(
+            negotiator::encodings(Some(header), &["gzip"]).is_empty(),
+            "{:?}",
+            header
+        );
+    }
+    assert_eq!(
+        negotiator::try_charsets(Some("\n*"), &provided),
+        Err(NegotiationError::ControlCharacter(0))
+    );
+    assert_eq!(
+        negotiator::try_charsets(Some("*\r\n"), &provided),
+        Err(NegotiationError::ControlCharacter(1))
+    );
+}
+
+#[test]
+fn negotiator_unicode_whitespace_wildcard() {
+    // Only spaces and tabs are optional whitespace, around a bare `*` too.
+    for header in &["\u{a0}*", "*\u{3000}"] {
+        assert!(
+            negotiator::charsets(Some(header), &["UTF-8"]).is_empty(),
+            "{:?}",
+            header
+        );
+        assert!(
+            !negotiator::is_encoding_acceptable(Some(header), "gzip"),
+            "{:?}",
+            header
+        );
+        assert!(
+            matches!(
+                negotiator::try_encodings(Some(header), &["gzip"]),
+                Err(NegotiationError::Malformed(negotiator::ParseError {
+                    kind: negotiator::ParseErrorKind::InvalidToken,
+                    ..
+                }))
+            ),
+            "{:?}",
+            header
+        );
+    }
+    assert_eq!(
+        negotiator::charsets(Some(" \t*\t "), &["UTF-8"]),
+        strings(&["UTF-8"])
+    );
+}
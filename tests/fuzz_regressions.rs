@@ -0,0 +1,41 @@
+//! Inputs which used to panic, found by the targets in fuzz/.
+
+#[test]
+fn negotiator_long_header_sort_no_underflow() {
+    let header: String = (0..2000)
+        .map(|i| format!("c{};q=0.{}", i, (i * 7919) % 10))
+        .collect::<Vec<String>>()
+        .join(",");
+    let provided: Vec<String> = (0..2000).map(|i| format!("c{}", i)).collect();
+    let provided: Vec<&str> = provided.iter().map(|p| p.as_str()).collect();
+    let options = negotiator::NegotiationOptions {
+        limits: negotiator::Limits {
+            max_header_len: usize::MAX,
+            max_entries: usize::MAX,
+            ..negotiator::Limits::default()
+        },
+        ..negotiator::NegotiationOptions::default()
+    };
+
+    assert_eq!(
+        negotiator::charset::acceptable_with_options(Some(&header), &options).len(),
+        1800
+    );
+    assert_eq!(
+        negotiator::charset::preferred_with_options(Some(&header), &provided, &options).len(),
+        1800
+    );
+}
+
+#[test]
+fn negotiator_extreme_quality_no_overflow() {
+    for header in &[
+        "a;q=-1e30, b;q=1e30",
+        "a;q=1e30, b;q=-1e30, a;q=-1e30",
+        "a;q=-inf, a;q=inf, *;q=NaN",
+    ] {
+        negotiator::charset::acceptable(Some(header));
+        negotiator::charset::preferred(Some(header), &["a", "b"]);
+        negotiator::charset::batch(vec![Some(*header)], &["a", "b"]);
+    }
+}
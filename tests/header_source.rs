@@ -0,0 +1,113 @@
+use negotiator::{HeaderSource, TokenOptions};
+
+const AVAILABLE: &[&str] = &["UTF-8", "ISO-8859-1", "KOI8-R"];
+
+#[test]
+fn header_source_slice() {
+    let headers: &[(&str, &str)] = &[
+        ("Accept", "text/html"),
+        ("accept-charset", "KOI8-R;q=0"),
+        ("Accept-Charset", "ISO-8859-1"),
+    ];
+
+    assert_eq!(
+        headers.get_all("Accept-Charset"),
+        vec!["KOI8-R;q=0", "ISO-8859-1"]
+    );
+    assert_eq!(
+        negotiator::combined_header(headers, "Accept-Charset"),
+        Some("KOI8-R;q=0, ISO-8859-1".to_string())
+    );
+    assert_eq!(
+        negotiator::charset::preferred_from_headers(headers, AVAILABLE),
+        negotiator::charset::preferred(Some("KOI8-R;q=0, ISO-8859-1"), AVAILABLE)
+    );
+    assert_eq!(
+        negotiator::charset::preferred_from_headers(headers, AVAILABLE),
+        vec!["ISO-8859-1".to_string()]
+    );
+}
+
+#[test]
+fn header_source_missing_header() {
+    let headers: &[(&str, &str)] = &[("Accept", "text/html")];
+
+    assert_eq!(negotiator::combined_header(headers, "Accept-Charset"), None);
+    assert_eq!(
+        negotiator::charset::preferred_from_headers(headers, AVAILABLE),
+        negotiator::charset::preferred(None, AVAILABLE)
+    );
+    assert_eq!(
+        negotiator::preferred_tokens_from_headers(
+            headers,
+            "Want-Digest",
+            &["sha-256"],
+            &TokenOptions::default()
+        ),
+        vec!["sha-256".to_string()]
+    );
+}
+
+#[test]
+fn header_source_tokens() {
+    let headers: &[(&str, &str)] = &[("Want-Digest", "sha-256;q=1, unixsum;q=0.1")];
+
+    assert_eq!(
+        negotiator::preferred_tokens_from_headers(
+            headers,
+            "want-digest",
+            &["unixsum", "sha-256"],
+            &TokenOptions::default()
+        ),
+        vec!["sha-256".to_string(), "unixsum".to_string()]
+    );
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn header_source_http_header_map() {
+    use http::header::{HeaderMap, HeaderValue, ACCEPT_CHARSET};
+
+    let mut map = HeaderMap::new();
+    map.append(ACCEPT_CHARSET, HeaderValue::from_static("KOI8-R;q=0"));
+    map.append(ACCEPT_CHARSET, HeaderValue::from_static("ISO-8859-1"));
+
+    let slice: &[(&str, &str)] = &[
+        ("Accept-Charset", "KOI8-R;q=0"),
+        ("Accept-Charset", "ISO-8859-1"),
+    ];
+
+    assert_eq!(
+        HeaderSource::get_all(&map, "accept-charset"),
+        slice.get_all("accept-charset")
+    );
+    assert_eq!(
+        negotiator::charset::preferred_from_headers(&map, AVAILABLE),
+        negotiator::charset::preferred_from_headers(slice, AVAILABLE)
+    );
+}
+
+#[cfg(feature = "http-types")]
+#[test]
+fn header_source_http_types_headers() {
+    use http_types::{Method, Request, Url};
+
+    let mut req = Request::new(Method::Get, Url::parse("http://example.com/").unwrap());
+    req.append_header("Accept-Charset", "KOI8-R;q=0");
+    req.append_header("Accept-Charset", "ISO-8859-1");
+    let headers: &http_types::Headers = req.as_ref();
+
+    let slice: &[(&str, &str)] = &[
+        ("Accept-Charset", "KOI8-R;q=0"),
+        ("Accept-Charset", "ISO-8859-1"),
+    ];
+
+    assert_eq!(
+        HeaderSource::get_all(headers, "accept-charset"),
+        slice.get_all("accept-charset")
+    );
+    assert_eq!(
+        negotiator::charset::preferred_from_headers(headers, AVAILABLE),
+        negotiator::charset::preferred_from_headers(slice, AVAILABLE)
+    );
+}
@@ -0,0 +1,273 @@
+//! The jshttp/negotiator test suites as data.
+//!
+//! Each axis has a fixture table and a runner. Only Accept-Charset is ported
+//! here; the upstream `encoding` suite is in `tests/encodings.rs`, and the
+//! other axes (`languages` and `mediaTypes` upstream) have no implementation
+//! in this crate yet.
+
+/// A single upstream assertion.
+struct Case {
+    /// The request header, `None` when the request has none.
+    header: Option<&'static str>,
+    /// The provided list, `None` when upstream calls without one.
+    provided: Option<&'static [&'static str]>,
+    /// The expected result, in order.
+    expected: &'static [&'static str],
+}
+
+const fn case(
+    header: Option<&'static str>,
+    provided: Option<&'static [&'static str]>,
+    expected: &'static [&'static str],
+) -> Case {
+    Case {
+        header,
+        provided,
+        expected,
+    }
+}
+
+/// `negotiator.charset()` and `negotiator.charset(array)`.
+const CHARSET: &[Case] = &[
+    case(None, None, &["*"]),
+    case(Some("*"), None, &["*"]),
+    case(Some("*, UTF-8"), None, &["*"]),
+    case(Some("*, UTF-8;q=0"), None, &["*"]),
+    case(Some("ISO-8859-1"), None, &["ISO-8859-1"]),
+    case(Some("UTF-8;q=0"), None, &[]),
+    case(Some("UTF-8, ISO-8859-1"), None, &["UTF-8"]),
+    case(Some("UTF-8;q=0.8, ISO-8859-1"), None, &["ISO-8859-1"]),
+    case(
+        Some("UTF-8;q=0.9, ISO-8859-1;q=0.8, UTF-8;q=0.7"),
+        None,
+        &["UTF-8"],
+    ),
+    case(None, Some(&[]), &[]),
+    case(None, Some(&["UTF-8"]), &["UTF-8"]),
+    case(None, Some(&["UTF-8", "ISO-8859-1"]), &["UTF-8"]),
+    case(Some("*"), Some(&[]), &[]),
+    case(Some("*"), Some(&["UTF-8"]), &["UTF-8"]),
+    case(Some("*"), Some(&["UTF-8", "ISO-8859-1"]), &["UTF-8"]),
+    case(Some("*, UTF-8"), Some(&["UTF-8"]), &["UTF-8"]),
+    case(Some("*, UTF-8"), Some(&["UTF-8", "ISO-8859-1"]), &["UTF-8"]),
+    case(
+        Some("*, UTF-8;q=0"),
+        Some(&["UTF-8", "ISO-8859-1"]),
+        &["ISO-8859-1"],
+    ),
+    case(Some("*, UTF-8;q=0"), Some(&["UTF-8"]), &[]),
+    case(Some("ISO-8859-1"), Some(&["ISO-8859-1"]), &["ISO-8859-1"]),
+    case(
+        Some("ISO-8859-1"),
+        Some(&["UTF-8", "ISO-8859-1"]),
+        &["ISO-8859-1"],
+    ),
+    case(Some("ISO-8859-1"), Some(&["iso-8859-1"]), &["iso-8859-1"]),
+    case(
+        Some("ISO-8859-1"),
+        Some(&["iso-8859-1", "ISO-8859-1"]),
+        &["iso-8859-1"],
+    ),
+    case(
+        Some("ISO-8859-1"),
+        Some(&["ISO-8859-1", "iso-8859-1"]),
+        &["ISO-8859-1"],
+    ),
+    case(Some("ISO-8859-1"), Some(&["utf-8"]), &[]),
+    case(Some("UTF-8;q=0"), Some(&["ISO-8859-1"]), &[]),
+    case(
+        Some("UTF-8;q=0"),
+        Some(&["UTF-8", "KOI8-R", "ISO-8859-1"]),
+        &[],
+    ),
+    case(Some("UTF-8;q=0"), Some(&["KOI8-R"]), &[]),
+    case(
+        Some("UTF-8, ISO-8859-1"),
+        Some(&["ISO-8859-1"]),
+        &["ISO-8859-1"],
+    ),
+    case(
+        Some("UTF-8, ISO-8859-1"),
+        Some(&["UTF-8", "KOI8-R", "ISO-8859-1"]),
+        &["UTF-8"],
+    ),
+    case(Some("UTF-8, ISO-8859-1"), Some(&["KOI8-R"]), &[]),
+    case(
+        Some("UTF-8;q=0.8, ISO-8859-1"),
+        Some(&["ISO-8859-1"]),
+        &["ISO-8859-1"],
+    ),
+    case(
+        Some("UTF-8;q=0.8, ISO-8859-1"),
+        Some(&["UTF-8", "KOI8-R", "ISO-8859-1"]),
+        &["ISO-8859-1"],
+    ),
+    case(
+        Some("UTF-8;q=0.8, ISO-8859-1"),
+        Some(&["UTF-8", "KOI8-R"]),
+        &["UTF-8"],
+    ),
+    case(
+        Some("UTF-8;q=0.9, ISO-8859-1;q=0.8, UTF-8;q=0.7"),
+        Some(&["ISO-8859-1"]),
+        &["ISO-8859-1"],
+    ),
+    case(
+        Some("UTF-8;q=0.9, ISO-8859-1;q=0.8, UTF-8;q=0.7"),
+        Some(&["UTF-8", "ISO-8859-1"]),
+        &["UTF-8"],
+    ),
+    case(
+        Some("UTF-8;q=0.9, ISO-8859-1;q=0.8, UTF-8;q=0.7"),
+        Some(&["ISO-8859-1", "UTF-8"]),
+        &["UTF-8"],
+    ),
+];
+
+/// `negotiator.charsets()` and `negotiator.charsets(array)`.
+const CHARSETS: &[Case] = &[
+    case(None, None, &["*"]),
+    case(Some("*"), None, &["*"]),
+    case(Some("*, UTF-8"), None, &["*", "UTF-8"]),
+    case(Some("*, UTF-8;q=0"), None, &["*"]),
+    case(Some("UTF-8;q=0"), None, &[]),
+    case(Some("ISO-8859-1"), None, &["ISO-8859-1"]),
+    case(Some("UTF-8, ISO-8859-1"), None, &["UTF-8", "ISO-8859-1"]),
+    case(
+        Some("UTF-8;q=0.8, ISO-8859-1"),
+        None,
+        &["ISO-8859-1", "UTF-8"],
+    ),
+    case(
+        Some("UTF-8;foo=bar;q=1, ISO-8859-1;q=1"),
+        None,
+        &["UTF-8", "ISO-8859-1"],
+    ),
+    case(
+        Some("UTF-8;q=0.9, ISO-8859-1;q=0.8, UTF-8;q=0.7"),
+        None,
+        &["UTF-8", "ISO-8859-1"],
+    ),
+    case(None, Some(&[]), &[]),
+    case(None, Some(&["UTF-8"]), &["UTF-8"]),
+    case(
+        None,
+        Some(&["UTF-8", "ISO-8859-1"]),
+        &["UTF-8", "ISO-8859-1"],
+    ),
+    case(Some("*"), Some(&[]), &[]),
+    case(Some("*"), Some(&["UTF-8"]), &["UTF-8"]),
+    case(
+        Some("*"),
+        Some(&["UTF-8", "ISO-8859-1"]),
+        &["UTF-8", "ISO-8859-1"],
+    ),
+    case(Some("*, UTF-8"), Some(&["UTF-8"]), &["UTF-8"]),
+    case(
+        Some("*, UTF-8"),
+        Some(&["UTF-8", "ISO-8859-1"]),
+        &["UTF-8", "ISO-8859-1"],
+    ),
+    case(Some("*, UTF-8;q=0"), Some(&["UTF-8"]), &[]),
+    case(
+        Some("*, UTF-8;q=0"),
+        Some(&["UTF-8", "ISO-8859-1"]),
+        &["ISO-8859-1"],
+    ),
+    case(Some("UTF-8;q=0"), Some(&["ISO-8859-1"]), &[]),
+    case(
+        Some("UTF-8;q=0"),
+        Some(&["UTF-8", "KOI8-R", "ISO-8859-1"]),
+        &[],
+    ),
+    case(Some("UTF-8;q=0"), Some(&["KOI8-R"]), &[]),
+    case(Some("ISO-8859-1"), Some(&["ISO-8859-1"]), &["ISO-8859-1"]),
+    case(
+        Some("ISO-8859-1"),
+        Some(&["UTF-8", "ISO-8859-1"]),
+        &["ISO-8859-1"],
+    ),
+    case(Some("ISO-8859-1"), Some(&["iso-8859-1"]), &["iso-8859-1"]),
+    case(
+        Some("ISO-8859-1"),
+        Some(&["iso-8859-1", "ISO-8859-1"]),
+        &["iso-8859-1", "ISO-8859-1"],
+    ),
+    case(
+        Some("ISO-8859-1"),
+        Some(&["ISO-8859-1", "iso-8859-1"]),
+        &["ISO-8859-1", "iso-8859-1"],
+    ),
+    case(Some("ISO-8859-1"), Some(&["utf-8"]), &[]),
+    case(
+        Some("UTF-8, ISO-8859-1"),
+        Some(&["ISO-8859-1"]),
+        &["ISO-8859-1"],
+    ),
+    case(
+        Some("UTF-8, ISO-8859-1"),
+        Some(&["UTF-8", "KOI8-R", "ISO-8859-1"]),
+        &["UTF-8", "ISO-8859-1"],
+    ),
+    case(Some("UTF-8, ISO-8859-1"), Some(&["KOI8-R"]), &[]),
+    case(
+        Some("UTF-8;q=0.8, ISO-8859-1"),
+        Some(&["ISO-8859-1"]),
+        &["ISO-8859-1"],
+    ),
+    case(
+        Some("UTF-8;q=0.8, ISO-8859-1"),
+        Some(&["UTF-8", "KOI8-R", "ISO-8859-1"]),
+        &["ISO-8859-1", "UTF-8"],
+    ),
+    case(Some("UTF-8;q=0.8, ISO-8859-1"), Some(&["KOI8-R"]), &[]),
+    case(
+        Some("UTF-8;q=0.9, ISO-8859-1;q=0.8, UTF-8;q=0.7"),
+        Some(&["ISO-8859-1"]),
+        &["ISO-8859-1"],
+    ),
+    case(
+        Some("UTF-8;q=0.9, ISO-8859-1;q=0.8, UTF-8;q=0.7"),
+        Some(&["UTF-8", "ISO-8859-1"]),
+        &["UTF-8", "ISO-8859-1"],
+    ),
+    case(
+        Some("UTF-8;q=0.9, ISO-8859-1;q=0.8, UTF-8;q=0.7"),
+        Some(&["ISO-8859-1", "UTF-8"]),
+        &["UTF-8", "ISO-8859-1"],
+    ),
+];
+
+/// Run every case.
+fn run(cases: &[Case], negotiate: impl Fn(Option<&str>, Option<&[&str]>) -> Vec<String>) {
+    for case in cases {
+        assert_eq!(
+            negotiate(case.header, case.provided),
+            case.expected,
+            "header: {:?}, provided: {:?}",
+            case.header,
+            case.provided
+        );
+    }
+}
+
+#[test]
+fn negotiator_jshttp_charset() {
+    run(CHARSET, |header, provided| match provided {
+        Some(provided) => negotiator::charset::most_preferred(header, provided)
+            .into_iter()
+            .collect(),
+        None => negotiator::charset::acceptable(header)
+            .into_iter()
+            .take(1)
+            .collect(),
+    });
+}
+
+#[test]
+fn negotiator_jshttp_charsets() {
+    run(CHARSETS, |header, provided| match provided {
+        Some(provided) => negotiator::charset::preferred(header, provided),
+        None => negotiator::charset::acceptable(header),
+    });
+}
@@ -0,0 +1,266 @@
+use negotiator::{Limits, NegotiationError, NegotiationOptions, TokenOptions};
+
+fn with_limits(limits: Limits) -> NegotiationOptions {
+    NegotiationOptions {
+        limits,
+        ..NegotiationOptions::default()
+    }
+}
+
+const PROVIDED: &[&str] = &["UTF-8", "ISO-8859-1", "KOI8-R"];
+
+#[test]
+fn negotiator_charsets_max_header_len() {
+    // The cut falls inside KOI8-R, which is dropped.
+    let header = "ISO-8859-1;q=0.5, UTF-8;q=0.8, KOI8-R";
+    let options = with_limits(Limits {
+        max_header_len: 33,
+        ..Limits::default()
+    });
+
+    assert_eq!(
+        negotiator::charset::preferred_with_options(Some(header), PROVIDED, &options),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::try_preferred_with_options(Some(header), PROVIDED, &options),
+        Err(NegotiationError::HeaderTooLong)
+    );
+
+    // A cut right before a comma keeps the entry before it.
+    let options = with_limits(Limits {
+        max_header_len: 16,
+        ..Limits::default()
+    });
+    assert_eq!(
+        negotiator::charset::preferred_with_options(Some(header), PROVIDED, &options),
+        vec!["ISO-8859-1".to_string()]
+    );
+}
+
+#[test]
+fn negotiator_charsets_max_entries() {
+    let header = "KOI8-R;q=0.1, ISO-8859-1;q=0.5, UTF-8";
+    let options = with_limits(Limits {
+        max_entries: 2,
+        ..Limits::default()
+    });
+
+    assert_eq!(
+        negotiator::charset::preferred_with_options(Some(header), PROVIDED, &options),
+        vec!["ISO-8859-1".to_string(), "KOI8-R".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::try_preferred_with_options(Some(header), PROVIDED, &options),
+        Err(NegotiationError::TooManyEntries)
+    );
+    assert_eq!(
+        negotiator::charset::try_preferred_with_options(
+            Some("KOI8-R, ISO-8859-1"),
+            PROVIDED,
+            &options
+        ),
+        Ok(vec!["KOI8-R".to_string(), "ISO-8859-1".to_string()])
+    );
+}
+
+#[test]
+fn negotiator_charsets_max_params_per_entry() {
+    let header = "UTF-8;a=1;q=0.1, ISO-8859-1;q=0.5";
+    let options = with_limits(Limits {
+        max_params_per_entry: 1,
+        ..Limits::default()
+    });
+
+    // The q after the limit is still read.
+    assert_eq!(
+        negotiator::charset::preferred_with_options(Some(header), PROVIDED, &options),
+        vec!["ISO-8859-1".to_string(), "UTF-8".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::try_preferred_with_options(Some(header), PROVIDED, &options),
+        Ok(vec!["ISO-8859-1".to_string(), "UTF-8".to_string()])
+    );
+    assert_eq!(
+        negotiator::charset::try_preferred_with_options(
+            Some("UTF-8;a=1;b=2;q=0.1"),
+            PROVIDED,
+            &options
+        ),
+        Err(NegotiationError::TooManyParams(0))
+    );
+
+    // An entry excluded after the limit stays excluded.
+    let params: Vec<String> = (0..20).map(|i| format!("p{}={}", i, i)).collect();
+    let excluded = format!("UTF-8;{};q=0, ISO-8859-1;q=0.5", params.join(";"));
+    assert_eq!(
+        negotiator::charset::preferred(Some(&excluded), PROVIDED),
+        vec!["ISO-8859-1".to_string()]
+    );
+    assert!(!negotiator::is_encoding_acceptable(
+        Some(&format!("gzip;{};q=0", params.join(";"))),
+        "gzip"
+    ));
+}
+
+#[test]
+fn negotiator_charsets_max_token_len() {
+    let header = "ISO-8859-1, UTF-8";
+    let options = with_limits(Limits {
+        max_token_len: 5,
+        ..Limits::default()
+    });
+
+    assert_eq!(
+        negotiator::charset::preferred_with_options(Some(header), PROVIDED, &options),
+        vec!["UTF-8".to_string()]
+    );
+    assert_eq!(
+        negotiator::charset::try_preferred_with_options(Some(header), PROVIDED, &options),
+        Err(NegotiationError::TokenTooLong(0))
+    );
+}
+
+#[test]
+fn negotiator_tokens_limits() {
+    let options = TokenOptions {
+        negotiation: with_limits(Limits {
+            max_entries: 1,
+            ..Limits::default()
+        }),
+        ..TokenOptions::default()
+    };
+    let header = Some("unixsum;q=0.1, sha-256");
+
+    assert_eq!(
+        negotiator::preferred_tokens_with_options(header, &["sha-256", "unixsum"], &options),
+        vec!["unixsum".to_string()]
+    );
+    assert_eq!(
+        negotiator::try_preferred_tokens_with_options(header, &["sha-256", "unixsum"], &options),
+        Err(NegotiationError::TooManyEntries)
+    );
+    assert_eq!(
+        negotiator::preferred_token_batch(vec![header], &["sha-256", "unixsum"], &options).selected,
+        vec![("sha-256".to_string(), 0), ("unixsum".to_string(), 1)]
+    );
+}
+
+#[test]
+fn negotiator_default_limits_are_generous() {
+    let header: String = (0..64)
+        .map(|i| format!("charset-{};q=0.5", i))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    assert_eq!(
+        negotiator::charset::try_preferred_with_options(
+            Some(&header),
+            &["charset-63"],
+            &NegotiationOptions::default()
+        ),
+        Ok(vec!["charset-63".to_string()])
+    );
+}
+
+#[test]
+fn negotiator_charsets_huge_header() {
+    let entries: Vec<String> = (0..10_000)
+        .map(|i| match i % 3 {
+            0 => format!("charset-{};q=0.{}", i, i % 9 + 1),
+            1 => "bad token".to_string(),
+            _ => format!("charset-{}", i),
+        })
+        .collect();
+    let header = entries.join(", ");
+    let names: Vec<String> = (0..10_000).map(|i| format!("charset-{}", i)).collect();
+    let provided: Vec<&str> = names.iter().map(String::as_str).collect();
+    let options = with_limits(Limits {
+        max_header_len: usize::MAX,
+        ..Limits::default()
+    });
+
+    // Only the first 64 entries are read, malformed ones included.
+    let preferred = negotiator::charset::preferred_with_options(Some(&header), &provided, &options);
+    assert_eq!(
+        preferred,
+        negotiator::charset::preferred_with_options(
+            Some(&entries[..64].join(", ")),
+            &provided,
+            &options
+        )
+    );
+    assert_eq!(preferred.len(), 43);
+    assert_eq!(
+        negotiator::charset::try_preferred_with_options(Some(&header), &provided, &options),
+        Err(NegotiationError::Malformed(negotiator::ParseError {
+            offset: 16,
+            entry: "bad token".to_string(),
+            kind: negotiator::ParseErrorKind::InvalidToken,
+        }))
+    );
+
+    let valid: Vec<String> = entries
+        .into_iter()
+        .filter(|entry| entry != "bad token")
+        .collect();
+    assert_eq!(
+        negotiator::charset::try_preferred_with_options(
+            Some(&valid.join(", ")),
+            &provided,
+            &options
+        ),
+        Err(NegotiationError::TooManyEntries)
+    );
+}
+
+#[test]
+fn negotiator_encodings_huge_header() {
+    use negotiator::encoding::{self, EncodingOptions};
+
+    let entries: Vec<String> = (0..50_000).map(|i| format!("junk-{}", i)).collect();
+    let header = entries.join(", ");
+    let provided = ["junk-99", "junk-100", "junk-49999", "gzip", "identity"];
+    let options = EncodingOptions {
+        negotiation: with_limits(Limits {
+            max_header_len: usize::MAX,
+            max_entries: 100,
+            ..Limits::default()
+        }),
+        ..EncodingOptions::default()
+    };
+
+    // Only the first 100 entries are read.
+    let preferred = encoding::preferred_with_options(Some(&header), &provided, &options);
+    assert_eq!(
+        preferred,
+        encoding::preferred_with_options(Some(&entries[..100].join(", ")), &provided, &options)
+    );
+    assert_eq!(preferred, vec!["junk-99", "identity"]);
+    assert_eq!(
+        encoding::try_preferred_with_options(Some(&header), &provided, &options),
+        Err(NegotiationError::TooManyEntries)
+    );
+    assert_eq!(
+        encoding::try_preferred_with_options(Some(&entries[..100].join(", ")), &provided, &options),
+        Ok(preferred)
+    );
+
+    // By default the header is cut at 8 KiB, and then at 64 entries.
+    assert_eq!(
+        negotiator::encodings(Some(&header), &provided),
+        vec!["identity"]
+    );
+    assert_eq!(
+        negotiator::encodings(Some(&header), &["junk-63", "junk-64"]),
+        vec!["junk-63"]
+    );
+    assert_eq!(
+        negotiator::try_encodings(Some(&header), &provided),
+        Err(NegotiationError::HeaderTooLong)
+    );
+    assert!(!negotiator::is_encoding_acceptable(
+        Some(&header),
+        "junk-64"
+    ));
+}
@@ -0,0 +1,108 @@
+use std::cmp::Ordering;
+
+use negotiator::{parse_tokens, token_priority, token_specificity, Specificity, TokenOptions};
+
+/// Every specificity a header of exact and wildcard entries can produce.
+fn specificities() -> Vec<Specificity> {
+    let qualities = ["0", "0.001", "0.5", "1", "2", "-1", "1e30", "-1e30", "NaN"];
+    let header: Vec<String> = qualities
+        .iter()
+        .flat_map(|q| vec![format!("UTF-8;q={}", q), format!("*;q={}", q)])
+        .collect();
+    let options = TokenOptions {
+        wildcard: true,
+        ..TokenOptions::default()
+    };
+
+    let mut specificities: Vec<Specificity> = parse_tokens(&header.join(", "))
+        .iter()
+        .filter_map(|range| token_specificity(range, "UTF-8", &options))
+        .collect();
+    specificities.push(Specificity::default());
+    specificities
+}
+
+#[test]
+fn negotiator_specificity_order_antisymmetric() {
+    let specificities = specificities();
+
+    for a in &specificities {
+        assert_eq!(a.compare(a), Ordering::Equal, "{:?}", a);
+        for b in &specificities {
+            assert_eq!(a.compare(b), b.compare(a).reverse(), "{:?} {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn negotiator_specificity_order_transitive() {
+    let specificities = specificities();
+
+    for a in &specificities {
+        for b in &specificities {
+            for c in &specificities {
+                if a.compare(b) != Ordering::Greater && b.compare(c) != Ordering::Greater {
+                    assert_ne!(a.compare(c), Ordering::Greater, "{:?} {:?} {:?}", a, b, c);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn negotiator_large_random_sort() {
+    // xorshift, so the header is the same on every run.
+    let mut state: u32 = 0x9e37_79b9;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    };
+
+    let header: Vec<String> = (0..2000)
+        .map(|_| {
+            let token = next() % 300;
+            let token = if token == 0 {
+                "*".to_string()
+            } else {
+                format!("charset-{}", token)
+            };
+            match next() % 4 {
+                0 => token,
+                1 => format!("{};q=0", token),
+                2 => format!("{};q=0.{}", token, next() % 1000),
+                _ => format!("{};q={}", token, next() as i32),
+            }
+        })
+        .collect();
+    let header = header.join(",");
+    let names: Vec<String> = (0..400).map(|i| format!("charset-{}", i)).collect();
+    let provided: Vec<&str> = names.iter().map(String::as_str).collect();
+
+    let preferred = negotiator::charset::preferred(Some(&header), &provided);
+    assert!(preferred.len() <= provided.len());
+    assert_eq!(
+        negotiator::charset::most_preferred(Some(&header), &provided),
+        preferred.into_iter().next()
+    );
+    negotiator::charset::acceptable(Some(&header));
+}
+
+#[test]
+fn negotiator_specificity_entry() {
+    let options = TokenOptions {
+        wildcard: true,
+        ..TokenOptions::default()
+    };
+    let ranges = parse_tokens("ISO-8859-1, , *;q=0.5, utf-8;q=0.2");
+
+    let exact = token_priority(&ranges, "UTF-8", &options).unwrap();
+    assert_eq!((exact.entry_index(), exact.is_exact()), (Some(3), true));
+    let wildcard = token_priority(&ranges, "UTF-16", &options).unwrap();
+    assert_eq!(
+        (wildcard.entry_index(), wildcard.is_exact()),
+        (Some(2), false)
+    );
+    assert_eq!(Specificity::default().entry_index(), None);
+}
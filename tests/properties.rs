@@ -0,0 +1,104 @@
+use proptest::prelude::*;
+
+const CHARSETS: &[&str] = &["UTF-8", "utf-8", "ISO-8859-1", "KOI8-R", "*"];
+const PROVIDED: &[&str] = &["UTF-8", "utf-8", "ISO-8859-1", "KOI8-R"];
+
+fn entry() -> impl Strategy<Value = String> {
+    (
+        prop::sample::select(CHARSETS),
+        prop::option::of(0u16..=1000),
+    )
+        .prop_map(|(charset, q)| match q {
+            Some(q) => format!("{};q={}", charset, f32::from(q) / 1000.0),
+            None => charset.to_string(),
+        })
+}
+
+fn header() -> impl Strategy<Value = String> {
+    prop::collection::vec(entry(), 0..6).prop_map(|entries| entries.join(", "))
+}
+
+fn provided() -> impl Strategy<Value = Vec<&'static str>> {
+    prop::collection::vec(prop::sample::select(PROVIDED), 1..5)
+}
+
+/// The quality the header gives a charset: exact entries beat `*`, then the highest q wins.
+fn effective_quality(header: &str, charset: &str) -> f32 {
+    let entries = negotiator::charset::parse(header);
+    let best = |exact: bool| {
+        entries
+            .iter()
+            .filter(|entry| {
+                if exact {
+                    entry.token().eq_ignore_ascii_case(charset)
+                } else {
+                    entry.token() == "*"
+                }
+            })
+            .map(|entry| entry.quality())
+            .fold(None, |best: Option<f32>, q| {
+                Some(best.map_or(q, |b| b.max(q)))
+            })
+    };
+    best(true).or_else(|| best(false)).unwrap_or(0.0)
+}
+
+/// Any header at all, biased towards the characters headers are made of.
+fn arbitrary_header() -> impl Strategy<Value = String> {
+    prop_oneof![any::<String>(), "[a-zA-Z0-9*;=,.q \t\r\n-]{0,40}"]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn negotiator_charsets_subset_of_provided(header in header(), provided in provided()) {
+        for charset in negotiator::charset::preferred(Some(&header), &provided) {
+            prop_assert!(provided.contains(&charset.as_str()));
+        }
+    }
+
+    #[test]
+    fn negotiator_charsets_deterministic(header in header(), provided in provided()) {
+        prop_assert_eq!(
+            negotiator::charset::preferred_with_quality(Some(&header), &provided),
+            negotiator::charset::preferred_with_quality(Some(&header), &provided)
+        );
+    }
+
+    #[test]
+    fn negotiator_charsets_never_excluded(header in header(), provided in provided()) {
+        for (charset, q) in negotiator::charset::preferred_with_quality(Some(&header), &provided) {
+            prop_assert!(q > 0.0);
+            prop_assert_eq!(q, effective_quality(&header, &charset));
+        }
+    }
+
+    #[test]
+    fn negotiator_charsets_canonical_round_trip(header in header()) {
+        let canonical = negotiator::charset::AcceptCharsetHeader::parse(&header).to_string();
+        let reparsed = negotiator::charset::AcceptCharsetHeader::parse(&canonical).to_string();
+        prop_assert_eq!(canonical, reparsed);
+    }
+
+    #[test]
+    fn negotiator_charsets_never_panic(
+        header in arbitrary_header(),
+        provided in prop::collection::vec(arbitrary_header(), 0..4),
+    ) {
+        let provided: Vec<&str> = provided.iter().map(String::as_str).collect();
+        for header in &[Some(header.as_str()), None] {
+            negotiator::charset::preferred_with_quality(*header, &provided);
+            negotiator::charset::most_preferred(*header, &provided);
+            negotiator::charset::acceptable(*header);
+            negotiator::charset::rejected(*header, &provided);
+            negotiator::charset::explain(*header, &provided);
+            negotiator::charset::negotiate(*header, &provided);
+            negotiator::charset::preferred_with_aliases(*header, &provided);
+            negotiator::charset::preferred_with_min_quality(*header, &provided, 0.5);
+            let _ = negotiator::try_charsets(*header, &provided);
+        }
+        negotiator::charset::AcceptCharsetHeader::parse(&header).to_string();
+        negotiator::header_stats(&header, negotiator::HeaderKind::AcceptCharset);
+    }
+}
@@ -0,0 +1,54 @@
+use negotiator::{header_stats, HeaderKind, HeaderStats};
+
+#[test]
+fn negotiator_header_stats_browser() {
+    assert_eq!(
+        header_stats("ISO-8859-1,utf-8;q=0.7,*;q=0.3", HeaderKind::AcceptCharset),
+        HeaderStats {
+            entries: 3,
+            malformed: 0,
+            max_token_len: 10,
+            params: 2,
+            has_wildcard: true,
+            has_exclusions: false,
+        }
+    );
+    assert!(!header_stats("*;q=0.3", HeaderKind::TokenList).has_wildcard);
+    assert_eq!(
+        header_stats("", HeaderKind::AcceptCharset),
+        HeaderStats::default()
+    );
+}
+
+#[test]
+fn negotiator_header_stats_pathological() {
+    let mut entries: Vec<String> = (0..500)
+        .map(|i| format!("charset-{};q=0.{};a=1;b=2", i, i % 10))
+        .collect();
+    entries.push("x".repeat(4096));
+    entries.push(";q=1".to_string());
+    entries.push("bad token".to_string());
+    entries.push("   ".to_string());
+    let header = entries.join(",");
+
+    let stats = header_stats(&header, HeaderKind::AcceptCharset);
+    assert_eq!(stats.entries, 501);
+    assert_eq!(stats.malformed, 2);
+    assert_eq!(stats.max_token_len, 4096);
+    assert_eq!(stats.params, 1500);
+    assert!(!stats.has_wildcard);
+    assert!(stats.has_exclusions);
+
+    // Stats see past the limits negotiation applies.
+    assert!(stats.entries > negotiator::Limits::default().max_entries);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn negotiator_header_stats_serialize() {
+    let stats = header_stats("UTF-8;q=0", HeaderKind::AcceptCharset);
+    assert_eq!(
+        serde_json::to_string(&stats).unwrap(),
+        r#"{"entries":1,"malformed":0,"max_token_len":5,"params":1,"has_wildcard":false,"has_exclusions":true}"#
+    );
+}
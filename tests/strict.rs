@@ -0,0 +1,188 @@
+use negotiator::{NegotiationError, ParseError, ParseErrorKind};
+
+const PROVIDED: &[&str] = &["UTF-8", "ISO-8859-1"];
+
+fn malformed(offset: usize, entry: &str, kind: ParseErrorKind) -> NegotiationError {
+    NegotiationError::Malformed(ParseError {
+        offset,
+        entry: entry.to_string(),
+        kind,
+    })
+}
+
+#[test]
+fn negotiator_try_charsets_malformed() {
+    let cases = [
+        (";q=1", malformed(0, ";q=1", ParseErrorKind::InvalidToken)),
+        (
+            "UTF-8;q=abc",
+            malformed(0, "UTF-8;q=abc", ParseErrorKind::InvalidQuality),
+        ),
+        (
+            "UTF-8;;q=1",
+            malformed(0, "UTF-8;;q=1", ParseErrorKind::EmptyParameter),
+        ),
+        (
+            "UTF-8, ;q=1",
+            malformed(6, ";q=1", ParseErrorKind::InvalidToken),
+        ),
+        (
+            "UTF-8,, ISO-8859-1",
+            malformed(6, "", ParseErrorKind::EmptyEntry),
+        ),
+        (
+            "UTF-8, ISO-8859-1,",
+            malformed(18, "", ParseErrorKind::EmptyEntry),
+        ),
+        (
+            "UTF-8;level",
+            malformed(0, "UTF-8;level", ParseErrorKind::MissingValue),
+        ),
+        (
+            "UTF-8;q=",
+            malformed(0, "UTF-8;q=", ParseErrorKind::InvalidQuality),
+        ),
+        (
+            "UTF-8;q=0.5=1",
+            malformed(0, "UTF-8;q=0.5=1", ParseErrorKind::InvalidQuality),
+        ),
+        (
+            "UTF-8;",
+            malformed(0, "UTF-8;", ParseErrorKind::EmptyParameter),
+        ),
+        (
+            "UTF-8;q=2",
+            malformed(0, "UTF-8;q=2", ParseErrorKind::InvalidQuality),
+        ),
+        (
+            "UTF-8, ISO-8859-1;q=-0.5",
+            malformed(6, "ISO-8859-1;q=-0.5", ParseErrorKind::InvalidQuality),
+        ),
+    ];
+
+    for (header, error) in &cases {
+        assert_eq!(
+            negotiator::try_charsets(Some(header), PROVIDED).as_ref(),
+            Err(error),
+            "{:?}",
+            header
+        );
+    }
+}
+
+#[test]
+fn negotiator_try_charsets_valid() {
+    assert_eq!(
+        negotiator::try_charsets(Some(" ISO-8859-1;level=1 ; q=0.5 , UTF-8 "), PROVIDED),
+        Ok(vec!["UTF-8".to_string(), "ISO-8859-1".to_string()])
+    );
+    assert_eq!(
+        negotiator::try_charsets(Some("ISO-8859-1;q=1.000, UTF-8;q=0"), PROVIDED),
+        Ok(vec!["ISO-8859-1".to_string()])
+    );
+    assert_eq!(negotiator::try_charsets(Some(""), PROVIDED), Ok(vec![]));
+    assert_eq!(
+        negotiator::try_charsets(None, PROVIDED),
+        Ok(negotiator::charsets(None, PROVIDED))
+    );
+
+    // Lenient negotiation skips what strict negotiation reports.
+    assert_eq!(
+        negotiator::charsets(Some(";q=1, UTF-8,, ISO-8859-1;;q=0.5"), PROVIDED),
+        vec!["UTF-8".to_string(), "ISO-8859-1".to_string()]
+    );
+}
+
+#[test]
+fn negotiator_parse_error_display() {
+    let error = negotiator::try_charsets(Some("UTF-8, ISO-8859-1;q=high"), PROVIDED).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "header entry \"ISO-8859-1;q=high\" at byte 6 has an invalid q-value"
+    );
+}
+
+#[test]
+fn negotiator_try_charsets_qvalue_grammar() {
+    for q in &["0", "0.", "0.8", "0.001", "0.999", "1", "1.", "1.000"] {
+        let header = format!("UTF-8;q={}", q);
+        assert!(
+            negotiator::try_charsets(Some(&header), PROVIDED).is_ok(),
+            "{:?}",
+            header
+        );
+    }
+    for q in &[
+        "0.1234", ".5", "1.0000", "1.001", "2", "-0", "01", "+1", "0.5e0", "NaN",
+    ] {
+        let header = format!("UTF-8;q={}", q);
+        assert_eq!(
+            negotiator::try_charsets(Some(&header), PROVIDED),
+            Err(malformed(0, &header, ParseErrorKind::InvalidQuality)),
+            "{:?}",
+            header
+        );
+    }
+}
+
+#[test]
+fn negotiator_try_encodings_malformed() {
+    let provided = ["gzip", "br"];
+    let cases = [
+        (
+            "gzip;;q=1",
+            malformed(0, "gzip;;q=1", ParseErrorKind::EmptyParameter),
+        ),
+        ("gz ip", malformed(0, "gz ip", ParseErrorKind::InvalidToken)),
+        (
+            "br, gz@ip;q=1",
+            malformed(3, "gz@ip;q=1", ParseErrorKind::InvalidToken),
+        ),
+        ("gzip, , br", malformed(5, "", ParseErrorKind::EmptyEntry)),
+        (
+            "gzip, br;q=high",
+            malformed(5, "br;q=high", ParseErrorKind::InvalidQuality),
+        ),
+        (
+            "gzip;q=0.5;q=1",
+            malformed(0, "gzip;q=0.5;q=1", ParseErrorKind::DuplicateQuality),
+        ),
+    ];
+
+    for (header, error) in &cases {
+        assert_eq!(
+            negotiator::try_encodings(Some(header), &provided).as_ref(),
+            Err(error),
+            "{:?}",
+            header
+        );
+    }
+
+    // Lenient negotiation skips what strict negotiation reports.
+    assert_eq!(
+        negotiator::encodings(Some("gzip;;q=1, gz ip, br;q=0.5"), &provided),
+        vec!["gzip", "br"]
+    );
+}
+
+#[test]
+fn negotiator_try_encodings_valid() {
+    let provided = ["identity", "gzip", "br"];
+    for header in &[
+        "gzip, deflate, br",
+        "br;q=1.0, x-gzip;q=0.5 , *;q=0",
+        "gzip;level=9;q=0.5",
+        "",
+    ] {
+        assert_eq!(
+            negotiator::try_encodings(Some(header), &provided),
+            Ok(negotiator::encodings(Some(header), &provided)),
+            "{:?}",
+            header
+        );
+    }
+    assert_eq!(
+        negotiator::try_encodings(None, &provided),
+        Ok(negotiator::encodings(None, &provided))
+    );
+}
@@ -0,0 +1,251 @@
+use negotiator::TokenOptions;
+
+const WANT_DIGEST: &str = "sha-256;q=1, unixsum;q=0.1";
+
+#[test]
+fn negotiator_preferred_tokens() {
+    assert_eq!(
+        negotiator::preferred_tokens(Some(WANT_DIGEST), &["unixsum", "sha-256", "md5"]),
+        vec!["sha-256".to_string(), "unixsum".to_string()]
+    );
+    assert_eq!(
+        negotiator::preferred_tokens(Some(WANT_DIGEST), &["md5"]),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        negotiator::preferred_tokens(Some(WANT_DIGEST), &[]),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        negotiator::acceptable_tokens(Some(WANT_DIGEST), &TokenOptions::default()),
+        vec!["sha-256".to_string(), "unixsum".to_string()]
+    );
+    assert_eq!(
+        negotiator::preferred_tokens(Some("SHA-256"), &["sha-256"]),
+        vec!["sha-256".to_string()]
+    );
+    assert_eq!(
+        negotiator::preferred_tokens(None, &["unixsum", "sha-256"]),
+        vec!["unixsum".to_string(), "sha-256".to_string()]
+    );
+}
+
+#[test]
+fn negotiator_preferred_tokens_exclusions() {
+    assert_eq!(
+        negotiator::preferred_tokens(Some("sha-256;q=0, unixsum"), &["sha-256", "unixsum"]),
+        vec!["unixsum".to_string()]
+    );
+    assert_eq!(
+        negotiator::acceptable_tokens(Some("sha-256;q=0"), &TokenOptions::default()),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn negotiator_preferred_tokens_wildcard() {
+    let wildcard = TokenOptions {
+        wildcard: true,
+        ..TokenOptions::default()
+    };
+
+    assert_eq!(
+        negotiator::preferred_tokens(Some("*"), &["sha-256"]),
+        Vec::<String>::new(),
+        "* is a plain token unless wildcards are enabled"
+    );
+    assert_eq!(
+        negotiator::preferred_tokens_with_options(Some("*"), &["sha-256"], &wildcard),
+        vec!["sha-256".to_string()]
+    );
+    assert_eq!(
+        negotiator::preferred_tokens_with_options(
+            Some("unixsum;q=0.5, *;q=0.1"),
+            &["md5", "sha-256"],
+            &wildcard
+        ),
+        vec!["md5".to_string(), "sha-256".to_string()]
+    );
+}
+
+#[test]
+fn negotiator_preferred_tokens_with_quality() {
+    assert_eq!(
+        negotiator::preferred_tokens_with_quality(
+            Some(WANT_DIGEST),
+            &["unixsum", "sha-256"],
+            &TokenOptions::default()
+        ),
+        vec![("sha-256".to_string(), 1.0), ("unixsum".to_string(), 0.1)]
+    );
+    assert_eq!(
+        negotiator::preferred_tokens_with_quality(None, &["sha-256"], &TokenOptions::default()),
+        vec![("sha-256".to_string(), 1.0)]
+    );
+}
+
+#[test]
+fn negotiator_preferred_token_where() {
+    let calls = std::cell::Cell::new(0);
+    let result = negotiator::preferred_token_where(
+        Some(WANT_DIGEST),
+        &["unixsum", "sha-256", "md5"],
+        &TokenOptions::default(),
+        |t| {
+            calls.set(calls.get() + 1);
+            t != "sha-256"
+        },
+    );
+    assert_eq!(result, Some("unixsum".to_string()));
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn negotiator_token_specificity() {
+    let wildcard = TokenOptions {
+        wildcard: true,
+        ..TokenOptions::default()
+    };
+    let ranges = negotiator::parse_tokens("*;q=0.5, utf-8;q=0.5, iso-8859-1;q=0");
+
+    assert_eq!(ranges.len(), 3);
+    assert_eq!(ranges[1].token(), "utf-8");
+    assert_eq!(ranges[1].quality(), 0.5);
+    assert_eq!(ranges[1].index(), 1);
+
+    let wild = negotiator::token_specificity(&ranges[0], "UTF-8", &wildcard).unwrap();
+    let exact = negotiator::token_specificity(&ranges[1], "UTF-8", &wildcard).unwrap();
+    assert_eq!(exact.compare(&wild), std::cmp::Ordering::Less);
+    assert_eq!(wild.compare(&exact), std::cmp::Ordering::Greater);
+
+    assert!(negotiator::token_specificity(&ranges[1], "KOI8-R", &wildcard).is_none());
+    assert!(
+        negotiator::token_specificity(&ranges[0], "KOI8-R", &TokenOptions::default()).is_none()
+    );
+
+    let excluded = negotiator::token_specificity(&ranges[2], "ISO-8859-1", &wildcard).unwrap();
+    assert_eq!(excluded.quality(), 0.0);
+    assert!(negotiator::token_priority(&ranges, "SHIFT_JIS", &TokenOptions::default()).is_none());
+}
+
+#[test]
+fn negotiator_token_priority_matches_charsets() {
+    let wildcard = TokenOptions {
+        wildcard: true,
+        ..TokenOptions::default()
+    };
+    let header = "ISO-8859-1;q=0.8, UTF-8, KOI8-R;q=0, windows-1252;q=0.9";
+    let provided = ["KOI8-R", "ISO-8859-1", "UTF-8", "SHIFT_JIS", "windows-1252"];
+
+    let ranges = negotiator::parse_tokens(header);
+    let mut ranked: Vec<(&str, negotiator::Specificity)> = provided
+        .iter()
+        .filter_map(|candidate| {
+            let spec = negotiator::token_priority(&ranges, candidate, &wildcard)?;
+            Some((*candidate, spec))
+        })
+        .filter(|(_, spec)| spec.quality() > 0.0)
+        .collect();
+    ranked.sort_by(|(_, a), (_, b)| a.compare(b));
+
+    let ranked: Vec<String> = ranked.iter().map(|(c, _)| c.to_string()).collect();
+    assert_eq!(
+        ranked,
+        negotiator::charset::preferred(Some(header), &provided)
+    );
+    assert_eq!(ranked, vec!["UTF-8", "windows-1252", "ISO-8859-1"]);
+}
+
+#[test]
+fn negotiator_tokens_normalize_case() {
+    let normalize = TokenOptions {
+        negotiation: negotiator::NegotiationOptions {
+            normalize_case: true,
+            ..negotiator::NegotiationOptions::default()
+        },
+        ..TokenOptions::default()
+    };
+    let provided = ["MD5", "SHA-256", "UnixSum"];
+
+    assert_eq!(
+        negotiator::preferred_tokens(Some(WANT_DIGEST), &provided),
+        vec!["SHA-256".to_string(), "UnixSum".to_string()]
+    );
+    assert_eq!(
+        negotiator::preferred_tokens_with_options(Some(WANT_DIGEST), &provided, &normalize),
+        vec!["sha-256".to_string(), "unixsum".to_string()]
+    );
+    assert_eq!(
+        negotiator::preferred_tokens_with_quality(Some(WANT_DIGEST), &provided, &normalize),
+        vec![("sha-256".to_string(), 1.0), ("unixsum".to_string(), 0.1)]
+    );
+    assert_eq!(
+        negotiator::preferred_token_where(Some(WANT_DIGEST), &provided, &normalize, |t| t
+            .starts_with("unix")),
+        Some("unixsum".to_string())
+    );
+
+    let summary = negotiator::preferred_token_batch(vec![Some(WANT_DIGEST)], &provided, &normalize);
+    assert!(summary.selected.contains(&("sha-256".to_string(), 1)));
+}
+
+#[test]
+fn negotiator_tokens_on_unmatched() {
+    use negotiator::{HeaderKind, NegotiationOptions};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let seen = count.clone();
+    let options = TokenOptions {
+        negotiation: NegotiationOptions {
+            on_unmatched: Some(Arc::new(move |kind: HeaderKind, token: &str| {
+                assert_eq!((kind, token), (HeaderKind::TokenList, "unixsum"));
+                seen.fetch_add(1, Ordering::SeqCst);
+            })),
+            ..NegotiationOptions::default()
+        },
+        ..TokenOptions::default()
+    };
+
+    negotiator::preferred_token_where(Some(WANT_DIGEST), &["sha-256"], &options, |_| true);
+    negotiator::preferred_token_batch(
+        vec![Some(WANT_DIGEST), Some("sha-256")],
+        &["sha-256"],
+        &options,
+    );
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn negotiator_tokens_ascii_case_insensitive() {
+    assert_eq!(
+        negotiator::preferred_tokens(Some("SHA-256;q=1, UnixSum;q=0.1"), &["unixsum", "Sha-256"]),
+        vec!["Sha-256".to_string(), "unixsum".to_string()]
+    );
+    assert_eq!(
+        negotiator::preferred_tokens(Some("ÄBC"), &["äbc", "ÄBC"]),
+        vec!["ÄBC".to_string()]
+    );
+}
+
+#[test]
+fn negotiator_tokens_bare_star_without_wildcard() {
+    assert!(negotiator::preferred_tokens(Some("*"), &["sha-256"]).is_empty());
+    assert_eq!(
+        negotiator::preferred_tokens(None, &["sha-256", "md5"]),
+        vec!["sha-256".to_string(), "md5".to_string()]
+    );
+}
+
+#[test]
+fn negotiator_parsed_tokens_into_owned() {
+    let owned: Vec<negotiator::AcceptToken<'static>> = {
+        let header = String::from("sha-256;q=1, unixsum;q=0.1");
+        let parsed = negotiator::parse_tokens(&header);
+        assert_eq!(parsed[1].token(), "unixsum");
+        parsed.into_iter().map(|token| token.into_owned()).collect()
+    };
+    assert_eq!(owned[0].to_string(), "sha-256");
+    assert_eq!(owned[1].quality(), 0.1);
+}
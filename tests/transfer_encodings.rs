@@ -0,0 +1,57 @@
+#[test]
+fn negotiator_transfer_encoding_trailers() {
+    let te = Some("trailers, deflate;q=0.5");
+    assert!(negotiator::te_accepts_trailers(te));
+    assert_eq!(
+        negotiator::transfer_encoding(te, &["gzip", "deflate", "chunked"]),
+        Some("deflate".to_string())
+    );
+    assert_eq!(
+        negotiator::transfer_encoding(te, &["gzip", "chunked"]),
+        Some("chunked".to_string())
+    );
+    assert_eq!(negotiator::transfer_encoding(te, &["trailers"]), None);
+    assert_eq!(
+        negotiator::transfer_encoding::preferred(te, &["chunked", "trailers", "deflate"]),
+        vec!["deflate", "chunked"]
+    );
+}
+
+#[test]
+fn negotiator_transfer_encoding_chunked() {
+    // chunked is always acceptable, if nothing else is.
+    assert_eq!(
+        negotiator::transfer_encoding(None, &["gzip", "chunked"]),
+        Some("chunked".to_string())
+    );
+    assert_eq!(negotiator::transfer_encoding(None, &["gzip"]), None);
+    assert_eq!(
+        negotiator::transfer_encoding(Some("chunked;q=0, gzip;q=0"), &["gzip", "chunked"]),
+        Some("chunked".to_string())
+    );
+    assert_eq!(
+        negotiator::transfer_encoding::preferred(Some("gzip;q=0.2, deflate"), &["CHUNKED", "gzip"]),
+        vec!["gzip", "CHUNKED"]
+    );
+
+    // TE has no wildcard.
+    assert_eq!(
+        negotiator::transfer_encoding(Some("*"), &["gzip", "chunked"]),
+        Some("chunked".to_string())
+    );
+    assert_eq!(
+        negotiator::transfer_encoding(Some("x-gzip;q=0.5"), &["gzip"]),
+        Some("gzip".to_string())
+    );
+}
+
+#[test]
+fn negotiator_te_accepts_trailers() {
+    assert!(!negotiator::te_accepts_trailers(None));
+    assert!(!negotiator::te_accepts_trailers(Some("")));
+    assert!(!negotiator::te_accepts_trailers(Some("deflate, chunked")));
+    assert!(negotiator::te_accepts_trailers(Some("Trailers")));
+    assert!(negotiator::te_accepts_trailers(Some(
+        "gzip;q=0.5 , trailers"
+    )));
+}
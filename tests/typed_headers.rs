@@ -0,0 +1,50 @@
+#![cfg(feature = "headers")]
+
+use headers::{Header, HeaderMap, HeaderMapExt, HeaderValue};
+use negotiator::charset::AcceptCharsetHeader;
+
+#[test]
+fn accept_charset_header_round_trip() {
+    let values = vec![
+        HeaderValue::from_static("UTF-8"),
+        HeaderValue::from_static("ISO-8859-1;q=0.5, KOI8-R;q=0"),
+    ];
+
+    let decoded = AcceptCharsetHeader::decode(&mut values.iter()).unwrap();
+    assert_eq!(decoded.entries().len(), 3);
+
+    let mut encoded = Vec::new();
+    decoded.encode(&mut encoded);
+    assert_eq!(
+        encoded,
+        vec![HeaderValue::from_static(
+            "UTF-8, ISO-8859-1;q=0.5, KOI8-R;q=0"
+        )]
+    );
+
+    let redecoded = AcceptCharsetHeader::decode(&mut encoded.iter()).unwrap();
+    assert_eq!(redecoded.to_string(), decoded.to_string());
+}
+
+#[test]
+fn accept_charset_header_typed_get() {
+    let mut map = HeaderMap::new();
+    map.typed_insert(AcceptCharsetHeader::parse("ISO-8859-1, UTF-8;q=0.5"));
+
+    assert_eq!(
+        map.get(http::header::ACCEPT_CHARSET)
+            .unwrap()
+            .to_str()
+            .unwrap(),
+        "ISO-8859-1, UTF-8;q=0.5"
+    );
+
+    let accept = map.typed_get::<AcceptCharsetHeader>().unwrap();
+    assert_eq!(
+        accept.preferred(&["UTF-8", "ISO-8859-1"]),
+        vec!["ISO-8859-1".to_string(), "UTF-8".to_string()]
+    );
+    assert!(HeaderMap::new()
+        .typed_get::<AcceptCharsetHeader>()
+        .is_none());
+}